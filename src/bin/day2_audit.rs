@@ -0,0 +1,37 @@
+use advent_of_code_2020::day2::{input_generator, AuditReport};
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let mut json = false;
+    let mut path = "input/2020/day2.txt".to_owned();
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--table" => json = false,
+            _ if arg.starts_with("--") => {
+                eprintln!("usage: day2_audit [--table|--json] [password database]");
+                process::exit(2);
+            }
+            _ => path = arg,
+        }
+    }
+
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let rows = input_generator(input.trim_end()).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let report = AuditReport::new(&rows);
+
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_table());
+    }
+}
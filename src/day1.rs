@@ -1,40 +1,208 @@
-use std::collections::HashSet;
+#[aoc_generator(day1)]
+pub fn input_generator(input: &str) -> Vec<u64> {
+    input.lines().map(|x| x.parse::<u64>().unwrap()).collect()
+}
 
-pub type Input = (Vec<u64>, HashSet<u64>);
+fn two_sum_sorted(xs: &[u64], target: u64) -> Option<Vec<u64>> {
+    let mut lo = 0;
+    let mut hi = xs.len().checked_sub(1)?;
 
-#[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Input {
-    let xs: Vec<u64> = input.lines().map(|x| x.parse::<u64>().unwrap()).collect();
-    let set: HashSet<u64> = xs.iter().cloned().collect();
+    while lo < hi {
+        match xs[lo].checked_add(xs[hi]) {
+            Some(sum) if sum == target => return Some(vec![xs[lo], xs[hi]]),
+            Some(sum) if sum < target => lo += 1,
+            _ => hi -= 1,
+        }
+    }
 
-    (xs, set)
+    None
 }
 
-#[aoc(day1, part1)]
-pub fn part1(input: &Input) -> u64 {
-    let (xs, set) = input;
+fn k_sum_sorted(xs: &[u64], k: usize, target: u64) -> Option<Vec<u64>> {
+    match k {
+        0 if target == 0 => Some(vec![]),
+        0 => None,
+        1 => xs.binary_search(&target).ok().map(|_| vec![target]),
+        2 => two_sum_sorted(xs, target),
+        _ => {
+            for (i, x) in xs.iter().enumerate() {
+                // Every other pick is at least `x`, so bigger firsts can't fit
+                match x.checked_mul(k as u64) {
+                    Some(least) if least <= target => (),
+                    _ => break,
+                }
+                if i > 0 && xs[i - 1] == *x {
+                    continue;
+                }
+
+                if let Some(mut rest) = k_sum_sorted(&xs[i + 1..], k - 1, target - x) {
+                    rest.insert(0, *x);
+                    return Some(rest);
+                }
+            }
 
-    for x in xs {
-        if set.contains(&(2020 - x)) {
-            return x * (2020 - x);
+            None
         }
     }
+}
+
+/// `k` entries of `values`, each used at most once, that add up to `target`,
+/// in ascending order. Fixes the smallest pick and recurses down to a
+/// two-pointer scan of the sorted values.
+pub fn find_k_sum(values: &[u64], k: usize, target: u64) -> Option<Vec<u64>> {
+    let mut xs = values.to_vec();
+    xs.sort_unstable();
 
-    0
+    k_sum_sorted(&xs, k, target)
 }
 
-#[aoc(day1, part2)]
-pub fn part2(input: &Input) -> u64 {
-    let (xs, set) = input;
-
-    for (i, x) in xs.iter().enumerate() {
-        for y in xs.iter().skip(i) {
-            let z = 2020 - x - y;
-            if set.contains(&z) {
-                return x * y * z;
+fn all_two_sums_sorted(
+    xs: &[(u64, usize)],
+    target: u64,
+    picked: &[usize],
+    found: &mut Vec<Vec<usize>>,
+) {
+    let mut push = |a: usize, b: usize| {
+        let mut combo = picked.to_vec();
+        combo.extend_from_slice(&[xs[a].1, xs[b].1]);
+        found.push(combo);
+    };
+    let mut lo = 0;
+    let mut hi = match xs.len().checked_sub(1) {
+        Some(hi) => hi,
+        None => return,
+    };
+
+    while lo < hi {
+        match xs[lo].0.checked_add(xs[hi].0) {
+            Some(sum) if sum < target => lo += 1,
+            Some(sum) if sum == target && xs[lo].0 == xs[hi].0 => {
+                // Everything in between is equal too, so any two of them match
+                for a in lo..hi {
+                    for b in a + 1..=hi {
+                        push(a, b);
+                    }
+                }
+                break;
+            }
+            Some(sum) if sum == target => {
+                let lo_end = lo + xs[lo..].iter().take_while(|x| x.0 == xs[lo].0).count();
+                let hi_start = hi + 1
+                    - xs[..=hi]
+                        .iter()
+                        .rev()
+                        .take_while(|x| x.0 == xs[hi].0)
+                        .count();
+
+                for a in lo..lo_end {
+                    for b in hi_start..=hi {
+                        push(a, b);
+                    }
+                }
+                lo = lo_end;
+                hi = hi_start - 1;
+            }
+            _ => hi -= 1,
+        }
+    }
+}
+
+fn all_k_sums_sorted(
+    xs: &[(u64, usize)],
+    k: usize,
+    target: u64,
+    picked: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    match k {
+        0 if target == 0 => found.push(picked.clone()),
+        0 => (),
+        2 => all_two_sums_sorted(xs, target, picked, found),
+        _ => {
+            for (i, (x, idx)) in xs.iter().enumerate() {
+                match x.checked_mul(k as u64) {
+                    Some(least) if least <= target => (),
+                    _ => break,
+                }
+
+                picked.push(*idx);
+                all_k_sums_sorted(&xs[i + 1..], k - 1, target - x, picked, found);
+                picked.pop();
             }
         }
     }
+}
+
+/// Every set of `k` distinct positions in `values` whose entries add up to
+/// `target`, as ascending indices. Equal entries at different positions make
+/// different combinations, and no entry is used twice.
+pub fn all_k_sums(values: &[u64], k: usize, target: u64) -> Vec<Vec<usize>> {
+    let mut xs: Vec<(u64, usize)> = values.iter().cloned().zip(0..).collect();
+    xs.sort_unstable();
+
+    let mut found = vec![];
+    all_k_sums_sorted(&xs, k, target, &mut vec![], &mut found);
+    for combo in found.iter_mut() {
+        combo.sort_unstable();
+    }
+    found.sort_unstable();
+
+    found
+}
+
+fn checked_product(xs: &[u64]) -> Option<u64> {
+    xs.iter().try_fold(1_u64, |acc, x| acc.checked_mul(*x))
+}
+
+#[aoc(day1, part1)]
+pub fn part1(input: &[u64]) -> Option<u64> {
+    find_k_sum(input, 2, 2020).and_then(|xs| checked_product(&xs))
+}
+
+#[aoc(day1, part2)]
+pub fn part2(input: &[u64]) -> Option<u64> {
+    find_k_sum(input, 3, 2020).and_then(|xs| checked_product(&xs))
+}
+
+#[test]
+pub fn test_find_k_sum() {
+    let xs = [1721, 979, 366, 299, 675, 1456];
+
+    assert_eq!(find_k_sum(&xs, 2, 2020), Some(vec![299, 1721]));
+    assert_eq!(find_k_sum(&xs, 3, 2020), Some(vec![366, 675, 979]));
+    assert_eq!(
+        find_k_sum(&xs, 4, 299 + 366 + 675 + 1721),
+        Some(vec![299, 366, 675, 1721])
+    );
+    assert_eq!(find_k_sum(&xs, 2, 5), None);
+    assert_eq!(find_k_sum(&xs, 0, 0), Some(vec![]));
+}
+
+#[test]
+pub fn test_find_k_sum_large_values() {
+    let xs = [u64::MAX, u64::MAX - 1, 3, 1];
+
+    assert_eq!(find_k_sum(&xs, 2, 4), Some(vec![1, 3]));
+    assert_eq!(find_k_sum(&xs, 3, 5), None);
+    assert_eq!(find_k_sum(&xs, 2, u64::MAX), Some(vec![1, u64::MAX - 1]));
+}
 
-    0
+#[test]
+pub fn test_all_k_sums() {
+    assert_eq!(all_k_sums(&[1010, 7], 2, 2020), Vec::<Vec<usize>>::new());
+    assert_eq!(part1(&[1010, 7]), None);
+    assert_eq!(all_k_sums(&[7, 1010, 1010], 2, 2020), vec![vec![1, 2]]);
+    assert_eq!(
+        all_k_sums(&[5, 5, 5], 2, 10),
+        vec![vec![0, 1], vec![0, 2], vec![1, 2]]
+    );
+    assert_eq!(
+        all_k_sums(&[1, 9, 1, 9, 5], 2, 10),
+        vec![vec![0, 1], vec![0, 3], vec![1, 2], vec![2, 3]]
+    );
+    assert_eq!(
+        all_k_sums(&[1721, 979, 366, 299, 675, 1456], 3, 2020),
+        vec![vec![1, 2, 4]]
+    );
+    assert_eq!(all_k_sums(&[2, 2, 2, 2], 3, 6).len(), 4);
 }
@@ -1,36 +1,445 @@
-use std::collections::BTreeSet;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// The biggest step in joltage an adapter can take.
+pub const MAX_GAP: u64 = 3;
+
+/// The outlet, every adapter in ascending order and the device, whose
+/// built-in adapter is rated `max_gap` above the highest one. `None` if the
+/// device rating doesn't fit in a `u64`.
+fn joltages(adapters: &[u64], max_gap: u64) -> Option<Vec<u64>> {
+    let mut joltages = Vec::with_capacity(adapters.len() + 2);
+    joltages.push(0);
+    joltages.extend_from_slice(adapters);
+    joltages.sort_unstable();
+
+    let device = joltages.last().unwrap().checked_add(max_gap)?;
+    joltages.push(device);
+
+    Some(joltages)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainError {
+    GapTooLarge { from: u64, to: u64, max_gap: u64 },
+    DeviceOutOfRange,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ChainError::GapTooLarge { from, to, max_gap } => write!(
+                f,
+                "can't go from {} to {} jolts, steps are at most {} jolts",
+                from, to, max_gap
+            ),
+            ChainError::DeviceOutOfRange => write!(f, "the device rating overflows"),
+        }
+    }
+}
+
+impl Error for ChainError {}
+
+/// Every adapter plugged in one after the other, from the outlet up to the
+/// device. Adapters with equal ratings are all kept and follow each other
+/// with a 0-jolt difference, which only the full chain allows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chain {
+    joltages: Vec<u64>,
+}
+
+impl Chain {
+    pub fn build(adapters: &[u64], max_gap: u64) -> Result<Self, ChainError> {
+        let joltages = joltages(adapters, max_gap).ok_or(ChainError::DeviceOutOfRange)?;
+
+        match joltages.windows(2).find(|w| w[1] - w[0] > max_gap) {
+            Some(w) => Err(ChainError::GapTooLarge {
+                from: w[0],
+                to: w[1],
+                max_gap,
+            }),
+            None => Ok(Self { joltages }),
+        }
+    }
+
+    /// The outlet, the adapters and the device, in order.
+    pub fn joltages(&self) -> &[u64] {
+        &self.joltages
+    }
+
+    pub fn adapters(&self) -> &[u64] {
+        &self.joltages[1..self.joltages.len() - 1]
+    }
+
+    pub fn device(&self) -> u64 {
+        *self.joltages.last().unwrap()
+    }
+
+    pub fn differences(&self) -> impl Iterator<Item = u64> + '_ {
+        self.joltages.windows(2).map(|w| w[1] - w[0])
+    }
+
+    /// How many steps of the chain have each difference.
+    pub fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
+        for d in self.differences() {
+            *histogram.entry(d).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+}
 
 #[aoc_generator(day10)]
-pub fn input_generator(input: &str) -> BTreeSet<u64> {
+pub fn input_generator(input: &str) -> Vec<u64> {
     input.lines().map(|x| x.parse::<u64>().unwrap()).collect()
 }
 
-fn differences(xs: &BTreeSet<u64>) -> impl Iterator<Item = u64> + '_ {
-    xs.iter().scan(0, |acc, x| {
-        let diff = *x - *acc;
-        *acc = *x;
+#[aoc(day10, part1)]
+pub fn part1(input: &[u64]) -> Result<usize, ChainError> {
+    let histogram = Chain::build(input, MAX_GAP)?.histogram();
+    let count = |d| histogram.get(&d).cloned().unwrap_or(0);
 
-        Some(diff)
-    })
+    Ok(count(1) * count(3))
 }
 
-#[aoc(day10, part1)]
-pub fn part1(input: &BTreeSet<u64>) -> usize {
-    let mut diffs = vec![0, 0, 1];
+/// Positions of `joltages` a chain can step to from position `i`.
+fn successors(joltages: &[u64], i: usize, max_gap: u64) -> impl Iterator<Item = usize> + '_ {
+    (i + 1..joltages.len())
+        .skip_while(move |j| joltages[*j] == joltages[i])
+        .take_while(move |j| joltages[*j] - joltages[i] <= max_gap)
+}
+
+/// For every position of `joltages`, the number of chains from there to the
+/// device. `None` if a count overflows.
+fn ways_to_device(joltages: &[u64], max_gap: u64) -> Option<Vec<u128>> {
+    let mut ways = vec![0_u128; joltages.len()];
+    *ways.last_mut()? = 1;
+
+    for i in (0..joltages.len() - 1).rev() {
+        ways[i] =
+            successors(joltages, i, max_gap).try_fold(0_u128, |acc, j| acc.checked_add(ways[j]))?;
+    }
+
+    Some(ways)
+}
+
+/// Ways to chain some of `adapters` from the outlet (0 jolts) to the device
+/// (`max_gap` above the highest adapter), each step going up by 1 to
+/// `max_gap` jolts. Adapters with the same rating are told apart but can't
+/// follow each other, in these counts as in the arrangements listed and
+/// sampled below. `None` if the count overflows.
+pub fn count_arrangements(adapters: &[u64], max_gap: u64) -> Option<u128> {
+    Some(ways_to_device(&joltages(adapters, max_gap)?, max_gap)?[0])
+}
+
+/// Every arrangement counted by `count_arrangements`, as the ratings of the
+/// adapters it uses, in lexicographic order. Branches that can't reach the
+/// device are never explored.
+///
+/// The walk goes through each rating once, from the first adapter with that
+/// rating, and an arrangement is yielded once per way of picking among the
+/// adapters sharing its ratings, so equal arrangements come out together.
+pub struct Arrangements {
+    joltages: Vec<u64>,
+    /// Where each position can go next, one position per rating, in the
+    /// order arrangements are yielded
+    stops: Vec<Vec<usize>>,
+    /// For the first adapter of each rating, how many adapters have it
+    copies: Vec<u128>,
+    /// The last arrangement yielded and how many more times to yield it
+    pending: (Vec<u64>, u128),
+    /// The positions of the current partial chain and how many of their stops
+    /// have been tried
+    path: Vec<(usize, usize)>,
+}
+
+impl Arrangements {
+    pub fn new(adapters: &[u64], max_gap: u64) -> Self {
+        let joltages = joltages(adapters, max_gap).unwrap_or_default();
+        let device = joltages.len().saturating_sub(1);
+
+        let mut copies = vec![1_u128; joltages.len()];
+        for i in (1..device.saturating_sub(1)).rev() {
+            if joltages[i] == joltages[i + 1] {
+                copies[i] = copies[i + 1] + 1;
+            }
+        }
+
+        let mut stops = vec![vec![]; joltages.len()];
+        let mut live = vec![false; joltages.len()];
+        for i in (0..joltages.len()).rev() {
+            // Adapters with the same rating lead to the same positions, so
+            // only the first of them is walked. Stopping at the device makes
+            // a prefix of every longer arrangement, so it sorts first
+            let mut next: Vec<usize> = successors(&joltages, i, max_gap)
+                .filter(|j| live[*j])
+                .collect();
+            next.dedup_by_key(|j| joltages[*j]);
+            next.sort_by_key(|j| *j != device);
+
+            live[i] = i == device || !next.is_empty();
+            stops[i] = next;
+        }
+
+        let path = if live.first() == Some(&true) {
+            vec![(0, 0)]
+        } else {
+            vec![]
+        };
+
+        Self {
+            joltages,
+            stops,
+            copies,
+            pending: (vec![], 0),
+            path,
+        }
+    }
+}
 
-    differences(input).for_each(|d| diffs[d as usize - 1] += 1);
+impl Iterator for Arrangements {
+    type Item = Vec<u64>;
 
-    diffs[0] * diffs[2]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.1 > 0 {
+            self.pending.1 -= 1;
+            return Some(self.pending.0.clone());
+        }
+
+        loop {
+            let (i, tried) = self.path.last_mut()?;
+            let device = self.joltages.len() - 1;
+            let next = match self.stops[*i].get(*tried) {
+                Some(next) => *next,
+                None => {
+                    self.path.pop();
+                    continue;
+                }
+            };
+            *tried += 1;
+
+            if next == device {
+                let adapters = &self.path[1..];
+                let arrangement: Vec<u64> =
+                    adapters.iter().map(|(i, _)| self.joltages[*i]).collect();
+                let copies = adapters
+                    .iter()
+                    .fold(1_u128, |acc, (i, _)| acc.saturating_mul(self.copies[*i]));
+
+                self.pending = (arrangement.clone(), copies - 1);
+                return Some(arrangement);
+            }
+            self.path.push((next, 0));
+        }
+    }
+}
+
+/// A small seedable SplitMix64 generator, enough to draw reproducible samples.
+#[derive(Clone, Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform value in `0..n`, rejecting draws that would bias the result.
+    pub fn below(&mut self, n: u128) -> u128 {
+        assert!(n > 0, "can't draw from an empty range");
+
+        // 2^128 mod n draws at the top of the range don't fill a whole 0..n
+        let rejected = (u128::MAX % n + 1) % n;
+        loop {
+            let x = u128::from(self.next_u64()) << 64 | u128::from(self.next_u64());
+            if x <= u128::MAX - rejected {
+                return x % n;
+            }
+        }
+    }
+}
+
+/// Draws arrangements uniformly, walking from the outlet and picking each
+/// next adapter in proportion to the arrangements it leads to.
+#[derive(Clone, Debug)]
+pub struct ArrangementSampler {
+    joltages: Vec<u64>,
+    ways: Vec<u128>,
+    max_gap: u64,
+}
+
+impl ArrangementSampler {
+    /// `None` if there are too many arrangements to count in a `u128`.
+    pub fn new(adapters: &[u64], max_gap: u64) -> Option<Self> {
+        let joltages = joltages(adapters, max_gap)?;
+        let ways = ways_to_device(&joltages, max_gap)?;
+
+        Some(Self {
+            joltages,
+            ways,
+            max_gap,
+        })
+    }
+
+    pub fn count(&self) -> u128 {
+        self.ways[0]
+    }
+
+    /// `None` if no arrangement reaches the device.
+    pub fn sample(&self, rng: &mut SplitMix64) -> Option<Vec<u64>> {
+        let device = self.joltages.len() - 1;
+        let mut arrangement = vec![];
+        let mut i = 0;
+
+        if self.count() == 0 {
+            return None;
+        }
+
+        while i != device {
+            let mut r = rng.below(self.ways[i]);
+            for j in successors(&self.joltages, i, self.max_gap) {
+                if r < self.ways[j] {
+                    i = j;
+                    break;
+                }
+                r -= self.ways[j];
+            }
+
+            if i != device {
+                arrangement.push(self.joltages[i]);
+            }
+        }
+
+        Some(arrangement)
+    }
 }
 
 #[aoc(day10, part2)]
-pub fn part2(input: &BTreeSet<u64>) -> u64 {
-    let perms = |cnt| 2_u64.pow(cnt as u32 - 1).min(7);
-    let result = differences(input).fold((1, 0), |(tot, cnt), x| match x {
-        1 => (tot, cnt + 1),
-        _ if cnt > 1 => (tot * perms(cnt), 0),
-        _ => (tot, 0),
-    });
-
-    result.0 * perms(result.1)
+pub fn part2(input: &[u64]) -> Option<u128> {
+    count_arrangements(input, MAX_GAP)
+}
+
+#[cfg(test)]
+const EXAMPLE: [u64; 11] = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+
+#[test]
+pub fn test_count_arrangements() {
+    assert_eq!(count_arrangements(&EXAMPLE, MAX_GAP), Some(8));
+    // A run of five 1-jolt steps, which the old closed form got wrong
+    assert_eq!(count_arrangements(&[1, 2, 3, 4, 5], 3), Some(13));
+    assert_eq!(count_arrangements(&[1, 2, 3, 4, 5], 1), Some(1));
+    assert_eq!(count_arrangements(&[2, 4, 5], 4), Some(3));
+    assert_eq!(count_arrangements(&[2, 4], 2), Some(1));
+    assert_eq!(count_arrangements(&[3], 2), Some(0));
+    assert_eq!(count_arrangements(&[3, 3], 3), Some(2));
+    assert_eq!(count_arrangements(&[], 3), Some(1));
+    assert_eq!(count_arrangements(&(1..=200).collect::<Vec<_>>(), 3), None);
+}
+
+#[test]
+pub fn test_chain() {
+    let chain = Chain::build(&EXAMPLE, MAX_GAP).unwrap();
+
+    assert_eq!(chain.device(), 22);
+    assert_eq!(chain.adapters().len(), EXAMPLE.len());
+    assert_eq!(
+        chain.histogram().into_iter().collect::<Vec<_>>(),
+        vec![(1, 7), (3, 5)]
+    );
+    assert_eq!(part1(&EXAMPLE), Ok(35));
+
+    let chain = Chain::build(&[1, 2, 2, 4], MAX_GAP).unwrap();
+    assert_eq!(chain.joltages(), &[0, 1, 2, 2, 4, 7]);
+    assert_eq!(
+        chain.histogram().into_iter().collect::<Vec<_>>(),
+        vec![(0, 1), (1, 2), (2, 1), (3, 1)]
+    );
+    assert_eq!(
+        Chain::build(&[1, 5, 6, 20], MAX_GAP),
+        Err(ChainError::GapTooLarge {
+            from: 1,
+            to: 5,
+            max_gap: 3
+        })
+    );
+    assert_eq!(
+        Chain::build(&[u64::MAX], u64::MAX),
+        Err(ChainError::DeviceOutOfRange)
+    );
+}
+
+#[test]
+pub fn test_checked_in_input() {
+    let input = input_generator(include_str!("../input/2020/day10.txt"));
+
+    assert_eq!(part2(&input), Some(226775649501184));
+}
+
+#[test]
+pub fn test_arrangements() {
+    let all: Vec<_> = Arrangements::new(&EXAMPLE, MAX_GAP).collect();
+
+    assert_eq!(all.len(), 8);
+    assert_eq!(all[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+    assert_eq!(all[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+    assert!(all.windows(2).all(|w| w[0] < w[1]));
+
+    let repeated = [1, 3, 3, 4, 4, 4, 6];
+    let all: Vec<_> = Arrangements::new(&repeated, MAX_GAP).collect();
+    assert_eq!(
+        all.len() as u128,
+        count_arrangements(&repeated, MAX_GAP).unwrap()
+    );
+    assert!(all.windows(2).all(|w| w[0] <= w[1]));
+    // One [1, 3, 4, 6] for each of the 2 * 3 picks of a 3 and a 4
+    assert!(all[..6].iter().all(|a| *a == [1, 3, 4, 6]));
+    assert_ne!(all[6], [1, 3, 4, 6]);
+    assert_eq!(
+        Arrangements::new(&[1, 2, 3], 3).collect::<Vec<_>>(),
+        vec![vec![1, 2, 3], vec![1, 3], vec![2, 3], vec![3]]
+    );
+    assert_eq!(
+        Arrangements::new(&[3, 3], 3).collect::<Vec<_>>(),
+        vec![vec![3], vec![3]]
+    );
+    assert_eq!(Arrangements::new(&[1, 5], 3).count(), 0);
+    assert_eq!(Arrangements::new(&[u64::MAX], 3).count(), 0);
+    assert_eq!(Arrangements::new(&[], 3).collect::<Vec<_>>(), vec![vec![]]);
+}
+
+#[test]
+pub fn test_sampler() {
+    let all: Vec<_> = Arrangements::new(&EXAMPLE, MAX_GAP).collect();
+    let sampler = ArrangementSampler::new(&EXAMPLE, MAX_GAP).unwrap();
+    let mut rng = SplitMix64::new(2020);
+    let mut seen = vec![0; all.len()];
+
+    assert_eq!(sampler.count(), 8);
+    for _ in 0..800 {
+        let sample = sampler.sample(&mut rng).unwrap();
+        seen[all.iter().position(|a| *a == sample).unwrap()] += 1;
+    }
+    assert!(seen.iter().all(|n| (50..150).contains(n)), "{:?}", seen);
+
+    assert_eq!(
+        sampler.sample(&mut SplitMix64::new(7)),
+        sampler.sample(&mut SplitMix64::new(7))
+    );
+    assert_eq!(ArrangementSampler::new(&[3, 3], 3).unwrap().count(), 2);
+    assert_eq!(
+        ArrangementSampler::new(&[1, 5], 3)
+            .unwrap()
+            .sample(&mut rng),
+        None
+    );
 }
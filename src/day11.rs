@@ -1,3 +1,4 @@
+use crate::grid::{Addressing, Grid, ParseGridError, ADJACENT};
 use std::fmt;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -19,90 +20,43 @@ impl fmt::Display for Cell {
     }
 }
 
-impl From<char> for Cell {
-    fn from(x: char) -> Self {
-        match x {
-            '.' => Cell::Floor,
-            'L' => Cell::EmptySeat,
-            '#' => Cell::TakenSeat,
-            _ => unreachable!(),
+impl Cell {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Cell::Floor),
+            'L' => Some(Cell::EmptySeat),
+            '#' => Some(Cell::TakenSeat),
+            _ => None,
         }
     }
 }
 
-type Pos = (i32, i32);
-const ADJACENT_POS: [Pos; 8] = [
-    (0, -1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-    (0, 1),
-    (-1, 1),
-    (-1, 0),
-    (-1, -1),
-];
-
 #[derive(Clone)]
 pub struct Map {
-    width: usize,
-    pub data: Vec<Cell>,
+    pub seats: Grid<Cell>,
 }
+
 impl fmt::Display for Map {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.data.iter().enumerate().for_each(|(i, x)| {
-            if i % self.width == 0 {
-                write!(f, "\n").unwrap();
-            }
-
-            write!(f, "{}", x).unwrap();
-        });
-
-        Ok(())
+        write!(f, "{}", self.seats)
     }
 }
-impl Map {
-    fn index_of(&self, pos: Pos) -> usize {
-        let (x, y) = pos;
-
-        y as usize * self.width + x as usize
-    }
-
-    fn pos_of(&self, idx: usize) -> Pos {
-        let x = idx % self.width;
-        let y = idx / self.width;
-
-        (x as i32, y as i32)
-    }
 
+impl Map {
     fn count_visible_taken_seats(&self, idx: usize, max_depth: usize) -> usize {
-        let (x, y) = self.pos_of(idx);
-
-        ADJACENT_POS.iter().fold(0, |cnt, (x_off, y_off)| {
-            let mut depth = 0;
-            let mut x = x;
-            let mut y = y;
-
-            loop {
-                depth += 1;
-                x += x_off;
-                y += y_off;
-
-                if depth > max_depth
-                    || x < 0
-                    || y < 0
-                    || x >= self.width as i32
-                    || y >= (self.data.len() / self.width) as i32
-                {
-                    break cnt;
-                }
-
-                unsafe {
-                    match self.data.get_unchecked(self.index_of((x, y))) {
-                        Cell::Floor => continue,
-                        Cell::EmptySeat => break cnt,
-                        Cell::TakenSeat => break cnt + 1,
-                    }
-                }
+        let pos = self.seats.pos_of(idx);
+
+        ADJACENT.iter().fold(0, |cnt, dir| {
+            let seat = self
+                .seats
+                .ray(pos, *dir, Addressing::BOUNDED)
+                .take(max_depth)
+                .map(|(_, cell)| *cell)
+                .find(|cell| *cell != Cell::Floor);
+
+            match seat {
+                Some(Cell::TakenSeat) => cnt + 1,
+                _ => cnt,
             }
         })
     }
@@ -124,46 +78,40 @@ impl Map {
     }
 
     pub fn step(&mut self, depth: usize, tollerance: usize) -> (usize, bool) {
-        let mut new_data = self.data.clone();
-
-        let result =
-            new_data
-                .iter_mut()
-                .enumerate()
-                .fold((0, false), |(taken, changes), (idx, seat)| match seat {
-                    Cell::Floor => (taken, changes),
-                    Cell::EmptySeat => {
-                        let new_seat = self.rule1(idx, depth);
-                        let changed = !matches!(new_seat, Cell::EmptySeat);
-
-                        *seat = new_seat;
-                        (taken + changed as usize, changes || changed)
-                    }
-                    Cell::TakenSeat => {
-                        let new_seat = self.rule2(idx, tollerance, depth);
-                        let changed = !matches!(new_seat, Cell::TakenSeat);
-
-                        *seat = new_seat;
-                        (taken + !changed as usize, changes || changed)
-                    }
-                });
-
-        self.data = new_data;
+        let mut new_seats = self.seats.clone();
+
+        let result = new_seats.cells_mut().iter_mut().enumerate().fold(
+            (0, false),
+            |(taken, changes), (idx, seat)| match seat {
+                Cell::Floor => (taken, changes),
+                Cell::EmptySeat => {
+                    let new_seat = self.rule1(idx, depth);
+                    let changed = !matches!(new_seat, Cell::EmptySeat);
+
+                    *seat = new_seat;
+                    (taken + changed as usize, changes || changed)
+                }
+                Cell::TakenSeat => {
+                    let new_seat = self.rule2(idx, tollerance, depth);
+                    let changed = !matches!(new_seat, Cell::TakenSeat);
+
+                    *seat = new_seat;
+                    (taken + !changed as usize, changes || changed)
+                }
+            },
+        );
+
+        self.seats = new_seats;
 
         result
     }
 }
 
 #[aoc_generator(day11)]
-pub fn input_generator(input: &str) -> Map {
-    let width = input.find('\n').unwrap();
-    let data = input
-        .lines()
-        .flat_map(|l| l.chars())
-        .map(Cell::from)
-        .collect();
-
-    Map { width, data }
+pub fn input_generator(input: &str) -> Result<Map, ParseGridError> {
+    let seats = Grid::parse(input, Cell::from_char)?;
+
+    Ok(Map { seats })
 }
 
 #[aoc(day11, part1)]
@@ -191,3 +139,35 @@ pub fn part2(input: &Map) -> usize {
         }
     }
 }
+
+#[test]
+pub fn test_example() {
+    let input = "L.LL.LL.LL
+LLLLLLL.LL
+L.L.L..L..
+LLLL.LL.LL
+L.LL.LL.LL
+L.LLLLL.LL
+..L.L.....
+LLLLLLLLLL
+L.LLLLLL.L
+L.LLLLL.LL";
+    let map = input_generator(input).unwrap();
+
+    assert_eq!(part1(&map), 37);
+    assert_eq!(part2(&map), 26);
+}
+
+#[test]
+pub fn test_ragged_row() {
+    let input = "L.LL\nLLL\nL.L.";
+
+    assert_eq!(
+        input_generator(input).err(),
+        Some(ParseGridError::RaggedRow {
+            row: 1,
+            expected: 4,
+            found: 3
+        })
+    );
+}
@@ -1,4 +1,8 @@
 use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg};
 
 pub type Pos = (isize, isize);
 
@@ -42,97 +46,385 @@ impl From<char> for Direction {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Direction::North => 'N',
+            Direction::East => 'E',
+            Direction::South => 'S',
+            Direction::West => 'W',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+            Direction::Forward => 'F',
+        };
+
+        write!(f, "{}", c)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Action {
     pub dir: Direction,
+    /// Distance for moves, degrees for turns.
     pub amount: usize,
 }
 
 impl Action {
     pub fn new(dir: Direction, amount: usize) -> Self {
-        match dir {
-            Direction::Left | Direction::Right => Self {
-                amount: amount / 90,
-                dir,
-            },
-            _ => Self { amount, dir },
+        Self { dir, amount }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.dir, self.amount)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NavigationError {
+    UnsupportedRotation { dir: Direction, degrees: usize },
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavigationError::UnsupportedRotation { dir, degrees } => write!(
+                f,
+                "can't turn {:?} by {} degrees with these coordinates",
+                dir, degrees
+            ),
         }
     }
 }
 
-#[derive(Debug)]
-pub struct WayPoint {
-    pub x_off: isize,
-    pub y_off: isize,
+impl Error for NavigationError {}
+
+/// The numbers a ferry's position and waypoint are kept in.
+pub trait Coordinate:
+    Copy + fmt::Debug + PartialEq + Add<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    fn from_isize(x: isize) -> Self;
+
+    fn abs(self) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    /// `v` turned counter-clockwise by `degrees`, in `0..360`, or `None` if
+    /// the result can't be represented.
+    fn rotate(v: (Self, Self), degrees: u32) -> Option<(Self, Self)>;
+}
+
+fn quarter_turns<C: Coordinate>((x, y): (C, C), turns: u32) -> (C, C) {
+    match turns % 4 {
+        0 => (x, y),
+        1 => (-y, x),
+        2 => (-x, -y),
+        _ => (y, -x),
+    }
+}
+
+/// Exact, but only for quarter turns.
+impl Coordinate for isize {
+    fn from_isize(x: isize) -> Self {
+        x
+    }
+
+    fn abs(self) -> Self {
+        isize::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn rotate(v: (Self, Self), degrees: u32) -> Option<(Self, Self)> {
+        if degrees.is_multiple_of(90) {
+            Some(quarter_turns(v, degrees / 90))
+        } else {
+            None
+        }
+    }
+}
+
+/// Any angle; quarter turns are still exact.
+impl Coordinate for f64 {
+    fn from_isize(x: isize) -> Self {
+        x as f64
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn rotate((x, y): (Self, Self), degrees: u32) -> Option<(Self, Self)> {
+        if degrees.is_multiple_of(90) {
+            return Some(quarter_turns((x, y), degrees / 90));
+        }
+
+        let (sin, cos) = f64::from(degrees).to_radians().sin_cos();
+        Some((x * cos - y * sin, x * sin + y * cos))
+    }
+}
+
+/// What `N`, `E`, `S` and `W` actions move, and where the waypoint starts.
+/// `L`, `R` and `F` always turn the waypoint and sail towards it.
+pub trait NavigationMode {
+    const START: Pos;
+
+    fn shift<C: Coordinate>(ferry: &mut Ferry<Self, C>, offset: (C, C))
+    where
+        Self: Sized;
+}
+
+/// The waypoint is the ferry's heading, and moves push the ferry itself.
+#[derive(Clone, Copy, Debug)]
+pub struct Heading;
+
+impl NavigationMode for Heading {
+    const START: Pos = (1, 0);
+
+    fn shift<C: Coordinate>(ferry: &mut Ferry<Self, C>, (dx, dy): (C, C)) {
+        ferry.x = ferry.x + dx;
+        ferry.y = ferry.y + dy;
+    }
+}
+
+/// Moves push the waypoint around the ferry.
+#[derive(Clone, Copy, Debug)]
+pub struct ViaWaypoint;
+
+impl NavigationMode for ViaWaypoint {
+    const START: Pos = (10, 1);
+
+    fn shift<C: Coordinate>(ferry: &mut Ferry<Self, C>, (dx, dy): (C, C)) {
+        ferry.waypoint.x_off = ferry.waypoint.x_off + dx;
+        ferry.waypoint.y_off = ferry.waypoint.y_off + dy;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WayPoint<C = isize> {
+    pub x_off: C,
+    pub y_off: C,
+}
+
+/// Where the ferry and its waypoint are after `action`, or at the start
+/// when there's no action.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoutePoint<C = isize> {
+    pub action: Option<Action>,
+    pub x: C,
+    pub y: C,
+    pub waypoint: WayPoint<C>,
 }
 
 #[derive(Debug)]
-pub struct Ferry {
-    pub x: isize,
-    pub y: isize,
-    pub waypoint: WayPoint,
+pub struct Ferry<M, C = isize> {
+    pub x: C,
+    pub y: C,
+    pub waypoint: WayPoint<C>,
+    route: Option<Vec<RoutePoint<C>>>,
+    mode: PhantomData<M>,
 }
 
-impl Default for Ferry {
+impl<M: NavigationMode, C: Coordinate> Default for Ferry<M, C> {
     fn default() -> Self {
         Self {
-            x: 0,
-            y: 0,
+            x: C::from_isize(0),
+            y: C::from_isize(0),
             waypoint: WayPoint {
-                x_off: 10,
-                y_off: 1,
+                x_off: C::from_isize(M::START.0),
+                y_off: C::from_isize(M::START.1),
             },
+            route: None,
+            mode: PhantomData,
         }
     }
 }
 
-impl Ferry {
-    fn move_ferry(&mut self, dir: Pos, amt: usize) {
-        let (dx, dy) = dir;
+impl<M: NavigationMode, C: Coordinate> Ferry<M, C> {
+    /// A ferry that keeps its starting point and every point it reaches.
+    pub fn recording() -> Self {
+        let mut ferry = Self::default();
+        ferry.route = Some(vec![ferry.route_point(None)]);
 
-        self.x += dx * amt as isize;
-        self.y += dy * amt as isize;
+        ferry
     }
 
-    fn move_waypoint(&mut self, dir: Pos, amt: usize) {
-        let (dx, dy) = dir;
-
-        self.waypoint.x_off += dx * amt as isize;
-        self.waypoint.y_off += dy * amt as isize;
+    fn route_point(&self, action: Option<Action>) -> RoutePoint<C> {
+        RoutePoint {
+            action,
+            x: self.x,
+            y: self.y,
+            waypoint: self.waypoint,
+        }
     }
 
-    fn forward(&mut self, amt: usize) {
-        let dx = self.waypoint.x_off;
-        let dy = self.waypoint.y_off;
+    /// `None` unless the ferry was made with `Ferry::recording`.
+    pub fn route(&self) -> Option<&[RoutePoint<C>]> {
+        self.route.as_deref()
+    }
 
-        self.x += dx * amt as isize;
-        self.y += dy * amt as isize;
+    fn forward(&mut self, amt: C) {
+        self.x = self.x + self.waypoint.x_off * amt;
+        self.y = self.y + self.waypoint.y_off * amt;
     }
 
-    fn rotate_waypoint(&mut self, dir: Direction, amt: usize) {
-        let amt = match dir {
-            Direction::Left => amt,
-            Direction::Right => 4 - amt,
+    fn rotate_waypoint(&mut self, dir: Direction, degrees: usize) -> Result<(), NavigationError> {
+        let turn = (degrees % 360) as u32;
+        let ccw = match dir {
+            Direction::Left => turn,
+            Direction::Right => (360 - turn) % 360,
             _ => unreachable!(),
         };
 
-        for _ in 0..amt {
-            let x = self.waypoint.x_off;
+        let (x_off, y_off) = C::rotate((self.waypoint.x_off, self.waypoint.y_off), ccw)
+            .ok_or(NavigationError::UnsupportedRotation { dir, degrees })?;
+        self.waypoint = WayPoint { x_off, y_off };
 
-            self.waypoint.x_off = -self.waypoint.y_off;
-            self.waypoint.y_off = x;
-        }
+        Ok(())
     }
 
-    pub fn step(&mut self, action: Action, part1: bool) {
-        let Action { dir, amount: amt } = action;
+    pub fn step(&mut self, action: Action) -> Result<(), NavigationError> {
+        let Action { dir, amount } = action;
+        let amt = C::from_isize(amount as isize);
+
         match dir {
-            Direction::Left | Direction::Right => self.rotate_waypoint(dir, amt),
+            Direction::Left | Direction::Right => self.rotate_waypoint(dir, amount)?,
             Direction::Forward => self.forward(amt),
-            _ if !part1 => self.move_waypoint(dir.try_into().unwrap(), amt),
-            _ => self.move_ferry(dir.try_into().unwrap(), amt),
+            _ => {
+                let (dx, dy): Pos = dir.try_into().unwrap();
+                M::shift(self, (C::from_isize(dx) * amt, C::from_isize(dy) * amt));
+            }
         };
+
+        let point = self.route_point(Some(action));
+        if let Some(route) = &mut self.route {
+            route.push(point);
+        }
+
+        Ok(())
+    }
+
+    pub fn sail(mut self, actions: &[Action]) -> Result<Self, NavigationError> {
+        for action in actions {
+            self.step(*action)?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn manhattan_distance(&self) -> C {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+/// Sails a new ferry through every action.
+pub fn navigate<M: NavigationMode, C: Coordinate>(
+    actions: &[Action],
+) -> Result<Ferry<M, C>, NavigationError> {
+    Ferry::default().sail(actions)
+}
+
+/// One line per point of `route`, starting with the header.
+pub fn to_csv<C: Coordinate + fmt::Display>(route: &[RoutePoint<C>]) -> String {
+    let mut csv = String::from("step,action,x,y,waypoint_x,waypoint_y\n");
+
+    for (step, p) in route.iter().enumerate() {
+        csv += &format!(
+            "{},{},{},{},{},{}\n",
+            step,
+            p.action.map(|a| a.to_string()).unwrap_or_default(),
+            p.x,
+            p.y,
+            p.waypoint.x_off,
+            p.waypoint.y_off,
+        );
+    }
+
+    csv
+}
+
+/// The route as an SVG polyline with north up, a green start marker, a red
+/// end marker and an arrow from every point to its waypoint. The view box
+/// fits everything drawn, and the longer side is 800 pixels.
+pub fn to_svg<C: Coordinate>(route: &[RoutePoint<C>]) -> String {
+    // SVG's y axis points down, so north is negative y. Subtracting from 0
+    // rather than negating keeps the origin from printing as -0
+    let points: Vec<(f64, f64)> = route
+        .iter()
+        .map(|p| (p.x.to_f64(), 0.0 - p.y.to_f64()))
+        .collect();
+    let tips: Vec<(f64, f64)> = route
+        .iter()
+        .zip(&points)
+        .map(|(p, (x, y))| (x + p.waypoint.x_off.to_f64(), y - p.waypoint.y_off.to_f64()))
+        .collect();
+
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (x, y) in points.iter().chain(&tips) {
+        min_x = min_x.min(*x);
+        min_y = min_y.min(*y);
+        max_x = max_x.max(*x);
+        max_y = max_y.max(*y);
     }
+    if points.is_empty() {
+        (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+
+    let size = (max_x - min_x).max(max_y - min_y).max(1.0);
+    let margin = size / 20.0;
+    let (width, height) = (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+    let scale = 800.0 / width.max(height);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+        min_x - margin,
+        min_y - margin,
+        width,
+        height,
+        (width * scale).round(),
+        (height * scale).round(),
+    );
+    svg += "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+            markerWidth=\"6\" markerHeight=\"6\" orient=\"auto\">\
+            <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"orange\"/></marker></defs>\n";
+
+    svg += "<g stroke=\"orange\" marker-end=\"url(#arrow)\">\n";
+    for ((x1, y1), (x2, y2)) in points.iter().zip(&tips) {
+        svg += &format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" vector-effect=\"non-scaling-stroke\"/>\n",
+            x1, y1, x2, y2
+        );
+    }
+    svg += "</g>\n";
+
+    let polyline: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+    svg += &format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"steelblue\" vector-effect=\"non-scaling-stroke\"/>\n",
+        polyline.join(" ")
+    );
+
+    let r = size / 100.0;
+    if let (Some((x, y)), Some((end_x, end_y))) = (points.first(), points.last()) {
+        svg += &format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"green\"/>\n",
+            x, y, r
+        );
+        svg += &format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"red\"/>\n",
+            end_x, end_y, r
+        );
+    }
+    svg += "</svg>\n";
+
+    svg
 }
 
 #[aoc_generator(day12)]
@@ -141,7 +433,7 @@ pub fn input_generator(input: &str) -> Vec<Action> {
         .lines()
         .map(|l| {
             let (dir, amount) = l.split_at(1);
-            let dir = Direction::from(dir.chars().nth(0).unwrap());
+            let dir = Direction::from(dir.chars().next().unwrap());
             let amount = amount.parse().unwrap();
 
             Action::new(dir, amount)
@@ -150,24 +442,85 @@ pub fn input_generator(input: &str) -> Vec<Action> {
 }
 
 #[aoc(day12, part1)]
-pub fn part1(input: &[Action]) -> usize {
-    let mut ferry = Ferry::default();
-    ferry.waypoint = WayPoint { x_off: 1, y_off: 0 };
-
-    for action in input {
-        ferry.step(*action, true);
-    }
+pub fn part1(input: &[Action]) -> Result<usize, NavigationError> {
+    let ferry = navigate::<Heading, isize>(input)?;
 
-    ferry.x.abs() as usize + ferry.y.abs() as usize
+    Ok(ferry.x.unsigned_abs() + ferry.y.unsigned_abs())
 }
 
 #[aoc(day12, part2)]
-pub fn part2(input: &[Action]) -> usize {
-    let mut ferry = Ferry::default();
+pub fn part2(input: &[Action]) -> Result<usize, NavigationError> {
+    let ferry = navigate::<ViaWaypoint, isize>(input)?;
 
-    for action in input {
-        ferry.step(*action, false);
-    }
+    Ok(ferry.x.unsigned_abs() + ferry.y.unsigned_abs())
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "F10\nN3\nF7\nR90\nF11";
+
+#[test]
+pub fn test_example() {
+    let actions = input_generator(EXAMPLE);
+
+    assert_eq!(part1(&actions), Ok(25));
+    assert_eq!(part2(&actions), Ok(286));
+    assert_eq!(
+        navigate::<ViaWaypoint, f64>(&actions)
+            .unwrap()
+            .manhattan_distance(),
+        286.0
+    );
+}
+
+#[test]
+pub fn test_arbitrary_rotations() {
+    let actions = input_generator("R45\nF10\nL405\nF10");
+
+    assert_eq!(
+        navigate::<Heading, isize>(&actions).err(),
+        Some(NavigationError::UnsupportedRotation {
+            dir: Direction::Right,
+            degrees: 45
+        })
+    );
+
+    let ferry = navigate::<Heading, f64>(&actions).unwrap();
+    let half = 10.0 / 2_f64.sqrt();
+    assert!((ferry.x - (half + 10.0)).abs() < 1e-9);
+    assert!((ferry.y + half).abs() < 1e-9);
+}
+
+#[test]
+pub fn test_route() {
+    let actions = input_generator(EXAMPLE);
+    let ferry = Ferry::<ViaWaypoint>::recording().sail(&actions).unwrap();
+    let route = ferry.route().unwrap();
+
+    assert_eq!(route.len(), actions.len() + 1);
+    assert_eq!(route[1].action, Some(actions[0]));
+    assert_eq!((route[5].x, route[5].y), (214, -72));
+    assert_eq!(
+        Ferry::<ViaWaypoint>::default()
+            .sail(&actions)
+            .unwrap()
+            .route(),
+        None
+    );
+
+    let csv = to_csv(route);
+    assert_eq!(
+        csv.lines().next(),
+        Some("step,action,x,y,waypoint_x,waypoint_y")
+    );
+    assert_eq!(csv.lines().nth(1), Some("0,,0,0,10,1"));
+    assert_eq!(csv.lines().last(), Some("5,F11,214,-72,4,-10"));
 
-    ferry.x.abs() as usize + ferry.y.abs() as usize
+    let svg = to_svg(route);
+    assert!(
+        svg.contains("viewBox=\"-10.9 -52.9 239.8 145.8\""),
+        "{}",
+        svg
+    );
+    assert!(svg.contains("points=\"0,0 100,-10 100,-10 170,-38 170,-38 214,72\""));
+    assert_eq!(svg.matches("<line ").count(), route.len());
 }
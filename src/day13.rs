@@ -46,5 +46,5 @@ pub fn part2_example() {
 
     assert!(busses
         .iter()
-        .all(|(i, x)| (time_stamp + *i as u64) % x == 0))
+        .all(|(i, x)| (time_stamp + *i as u64).is_multiple_of(*x)))
 }
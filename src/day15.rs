@@ -56,15 +56,15 @@ pub fn input_generator(input: &str) -> Vec<u64> {
 }
 
 #[aoc(day15, part1)]
-pub fn part1(input: &Vec<u64>) -> u64 {
-    let mut seq = MemoryGame::new(&input);
+pub fn part1(input: &[u64]) -> u64 {
+    let mut seq = MemoryGame::new(input);
 
     seq.nth(2020).unwrap()
 }
 
 #[aoc(day15, part2)]
-pub fn part2(input: &Vec<u64>) -> u64 {
-    let mut seq = MemoryGame::new(&input);
+pub fn part2(input: &[u64]) -> u64 {
+    let mut seq = MemoryGame::new(input);
 
     seq.nth(30_000_000).unwrap()
 }
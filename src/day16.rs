@@ -1,6 +1,8 @@
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 
+use crate::records::records;
+
 lazy_static! {
     static ref RE_FIELD: Regex = Regex::new(r"(\w+ ?\w*): (\d+)-(\d+) or (\d+)-(\d+)").unwrap();
 }
@@ -18,10 +20,15 @@ fn parse_ticket(ticket: &str) -> Vec<u64> {
 pub fn input_generator(
     input: &str,
 ) -> (HashMap<String, NonContinuousRange>, Vec<u64>, Vec<Vec<u64>>) {
-    let mut paragraphs = input.split("\n\n");
+    let mut paragraphs = records(input);
     // parse ticket fields
     let mut fields: HashMap<String, NonContinuousRange> = HashMap::new();
-    for capture in RE_FIELD.captures_iter(paragraphs.next().unwrap()) {
+    for capture in paragraphs
+        .next()
+        .unwrap()
+        .iter()
+        .flat_map(|line| RE_FIELD.captures_iter(line))
+    {
         fields.insert(
             capture[1].to_owned(),
             (
@@ -33,15 +40,15 @@ pub fn input_generator(
         );
     }
     // parse my ticket
-    let my_ticket = parse_ticket(paragraphs.next().unwrap().lines().skip(1).next().unwrap());
+    let my_ticket = parse_ticket(paragraphs.next().unwrap()[1]);
 
     //parse other tickets
     let other_tickets = paragraphs
         .next()
         .unwrap()
-        .lines()
+        .into_iter()
         .skip(1)
-        .map(|t| parse_ticket(t))
+        .map(parse_ticket)
         .collect();
 
     (fields, my_ticket, other_tickets)
@@ -78,7 +85,7 @@ pub fn part2(
     (fields, my_ticket, tickets): &(HashMap<String, NonContinuousRange>, Vec<u64>, Vec<Vec<u64>>),
 ) -> u64 {
     let tickets: Vec<&Vec<u64>> = tickets
-        .into_iter()
+        .iter()
         .filter(|t| validate_ticket(t, fields).is_none())
         .collect();
     let mut valid_col_field: Vec<HashSet<&str>> = vec![HashSet::new(); my_ticket.len()];
@@ -106,9 +113,9 @@ pub fn part2(
             .for_each(|(i, f)| {
                 field_pos.insert(f.iter().next().unwrap(), i);
             });
-        for i in 0..valid_col_field.len() {
+        for valid_fields in valid_col_field.iter_mut() {
             for field in field_pos.keys() {
-                valid_col_field[i].remove(field);
+                valid_fields.remove(field);
             }
         }
     }
@@ -12,7 +12,7 @@ fn solve_simple_expr(expr: &str, with_precedence: bool) -> u64 {
         let first_operand = operands[0];
         operators
             .into_iter()
-            .zip(operands[1..].into_iter())
+            .zip(operands[1..].iter())
             .fold(first_operand, |acc, (op, x)| match op {
                 '+' => acc + x,
                 '*' => acc * x,
@@ -34,8 +34,8 @@ fn solve_simple_expr(expr: &str, with_precedence: bool) -> u64 {
     }
 }
 
-fn remove_sub_expr(expr: &String, precedence: bool) -> String {
-    let mut expr = expr.clone();
+fn remove_sub_expr(expr: &str, precedence: bool) -> String {
+    let mut expr = expr.to_owned();
     let mut parentheses = Vec::<usize>::new();
 
     let mut i = 0;
@@ -61,7 +61,7 @@ fn remove_sub_expr(expr: &String, precedence: bool) -> String {
     expr
 }
 
-fn solve(expr: &String, precedence: bool) -> u64 {
+fn solve(expr: &str, precedence: bool) -> u64 {
     solve_simple_expr(remove_sub_expr(expr, precedence).as_str(), precedence)
 }
 
@@ -72,10 +72,10 @@ pub fn input_generator(input: &str) -> Vec<String> {
 
 #[aoc(day18, part1)]
 pub fn part1(input: &[String]) -> u64 {
-    input.into_iter().map(|e| solve(e, false)).sum()
+    input.iter().map(|e| solve(e, false)).sum()
 }
 
 #[aoc(day18, part2)]
 pub fn part2(input: &[String]) -> u64 {
-    input.into_iter().map(|e| solve(e, true)).sum()
+    input.iter().map(|e| solve(e, true)).sum()
 }
@@ -1,40 +1,563 @@
+use crate::export::json_string;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Positions and counts are in `char`s, so a multi-byte character is one
+/// position but a letter followed by a combining accent is two.
+pub trait PasswordPolicy {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError>;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolicyError {
+    PositionOutOfRange { position: usize, len: usize },
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PolicyError::PositionOutOfRange { position, len } => write!(
+                f,
+                "position {} is outside a password of {} characters",
+                position, len
+            ),
+        }
+    }
+}
+
+impl Error for PolicyError {}
+
+/// `c` appears between `min` and `max` times.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CountRange {
+    pub min: usize,
+    pub max: usize,
+    pub c: char,
+}
+
+impl PasswordPolicy for CountRange {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        let count = pwd.chars().filter(|x| *x == self.c).count();
+
+        Ok(count >= self.min && count <= self.max)
+    }
+}
+
+/// `c` is at exactly one of the two 1-based positions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PositionalXor {
+    pub first: usize,
+    pub second: usize,
+    pub c: char,
+}
+
+impl PasswordPolicy for PositionalXor {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        let len = pwd.chars().count();
+        let at = |position: usize| {
+            if position == 0 || position > len {
+                Err(PolicyError::PositionOutOfRange { position, len })
+            } else {
+                Ok(pwd.chars().nth(position - 1) == Some(self.c))
+            }
+        };
+
+        Ok(at(self.first)? ^ at(self.second)?)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    fn contains(self, c: char) -> bool {
+        match self {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+/// At least one character of every class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequiredClasses(pub Vec<CharClass>);
+
+impl PasswordPolicy for RequiredClasses {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        Ok(self
+            .0
+            .iter()
+            .all(|class| pwd.chars().any(|c| class.contains(c))))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForbiddenSubstrings(pub Vec<String>);
+
+impl PasswordPolicy for ForbiddenSubstrings {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        Ok(!self.0.iter().any(|s| pwd.contains(s.as_str())))
+    }
+}
+
+/// Shannon entropy of the password's own character distribution, in bits
+/// per character times its length, is at least the given amount.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinEntropy(pub f64);
+
+fn entropy_bits(pwd: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in pwd.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    let len = counts.values().sum::<usize>() as f64;
+
+    counts
+        .values()
+        .map(|n| {
+            let p = *n as f64 / len;
+            -p * p.log2() * len
+        })
+        .sum()
+}
+
+impl PasswordPolicy for MinEntropy {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        Ok(entropy_bits(pwd) >= self.0)
+    }
+}
+
+/// No character repeats more than the given number of times in a row.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaxRun(pub usize);
+
+impl PasswordPolicy for MaxRun {
+    fn check(&self, pwd: &str) -> Result<bool, PolicyError> {
+        let mut run = (None, 0);
+
+        Ok(pwd.chars().all(|c| {
+            run = match run {
+                (Some(last), n) if last == c => (Some(c), n + 1),
+                _ => (Some(c), 1),
+            };
+
+            run.1 <= self.0
+        }))
+    }
+}
+
+/// The puzzle's `<a>-<b> <c>` policy, read as a `CountRange` by part 1 and as
+/// a `PositionalXor` by part 2.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Policy(usize, usize, char);
 
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} {}", self.0, self.1, self.2)
+    }
+}
+
 impl Policy {
-    pub fn new(s: &str) -> Self {
-        let mut policy = Policy(0, 0, ' ');
+    pub fn count_range(&self) -> CountRange {
+        CountRange {
+            min: self.0,
+            max: self.1,
+            c: self.2,
+        }
+    }
 
-        let mut values = s.split(|c| c == '-' || c == ' ');
+    pub fn positional(&self) -> PositionalXor {
+        PositionalXor {
+            first: self.0,
+            second: self.1,
+            c: self.2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParsePolicyError {
+    MissingPassword,
+    UnknownKind(String),
+    InvalidArguments(String),
+    InvalidRange(String),
+    InvalidChar(String),
+}
 
-        policy.0 = values.next().unwrap().parse().unwrap();
-        policy.1 = values.next().unwrap().parse().unwrap();
-        policy.2 = values.next().unwrap().chars().nth(0).unwrap();
+impl fmt::Display for ParsePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePolicyError::MissingPassword => write!(f, "missing \": <password>\""),
+            ParsePolicyError::UnknownKind(kind) => write!(f, "unknown policy kind {:?}", kind),
+            ParsePolicyError::InvalidArguments(args) => {
+                write!(f, "invalid policy arguments {:?}", args)
+            }
+            ParsePolicyError::InvalidRange(range) => write!(
+                f,
+                "expected a range like \"1-3\" of non-negative numbers, found {:?}",
+                range
+            ),
+            ParsePolicyError::InvalidChar(c) => {
+                write!(f, "expected a single character, found {:?}", c)
+            }
+        }
+    }
+}
+
+impl Error for ParsePolicyError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyFileError {
+    pub line: usize,
+    pub error: ParsePolicyError,
+}
+
+impl fmt::Display for PolicyFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for PolicyFileError {}
+
+fn parse_char_range(args: &str) -> Result<(usize, usize, char), ParsePolicyError> {
+    let (range, c) = args
+        .split_once(' ')
+        .ok_or_else(|| ParsePolicyError::InvalidArguments(args.to_owned()))?;
+    let invalid_range = || ParsePolicyError::InvalidRange(range.to_owned());
+    let (a, b) = range.split_once('-').ok_or_else(invalid_range)?;
+    let a = a.parse().map_err(|_| invalid_range())?;
+    let b = b.parse().map_err(|_| invalid_range())?;
+
+    let mut chars = c.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok((a, b, c)),
+        _ => Err(ParsePolicyError::InvalidChar(c.to_owned())),
+    }
+}
+
+impl FromStr for Policy {
+    type Err = ParsePolicyError;
+
+    fn from_str(input: &str) -> Result<Policy, Self::Err> {
+        let (a, b, c) = parse_char_range(input)?;
+
+        Ok(Policy(a, b, c))
+    }
+}
 
-        policy
+fn parse_class(class: &str) -> Option<CharClass> {
+    match class {
+        "lower" => Some(CharClass::Lower),
+        "upper" => Some(CharClass::Upper),
+        "digit" => Some(CharClass::Digit),
+        "symbol" => Some(CharClass::Symbol),
+        _ => None,
     }
 }
 
+pub type PolicyEntry = (Box<dyn PasswordPolicy>, String);
+
+/// Parses a policy-file line, `<kind> <arguments>: <password>`, where kind
+/// and arguments are one of
+///
+/// - `count <min>-<max> <char>`
+/// - `xor <pos>-<pos> <char>`
+/// - `classes <lower|upper|digit|symbol>,...`
+/// - `forbid <substring>,...`
+/// - `entropy <bits>`
+/// - `max-run <length>`
+pub fn parse_policy_line(line: &str) -> Result<PolicyEntry, ParsePolicyError> {
+    let (policy, pwd) = line
+        .split_once(": ")
+        .ok_or(ParsePolicyError::MissingPassword)?;
+    let (kind, args) = policy.split_once(' ').unwrap_or((policy, ""));
+    let invalid = || ParsePolicyError::InvalidArguments(args.to_owned());
+
+    let policy: Box<dyn PasswordPolicy> = match kind {
+        "count" => {
+            let (min, max, c) = parse_char_range(args)?;
+            Box::new(CountRange { min, max, c })
+        }
+        "xor" => {
+            let (first, second, c) = parse_char_range(args)?;
+            Box::new(PositionalXor { first, second, c })
+        }
+        "classes" => Box::new(RequiredClasses(
+            args.split(',')
+                .map(parse_class)
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?,
+        )),
+        "forbid" if !args.is_empty() => Box::new(ForbiddenSubstrings(
+            args.split(',').map(str::to_owned).collect(),
+        )),
+        "entropy" => Box::new(MinEntropy(args.parse().map_err(|_| invalid())?)),
+        "max-run" => Box::new(MaxRun(args.parse().map_err(|_| invalid())?)),
+        "forbid" => return Err(invalid()),
+        _ => return Err(ParsePolicyError::UnknownKind(kind.to_owned())),
+    };
+
+    Ok((policy, pwd.to_owned()))
+}
+
+pub fn parse_policy_file(input: &str) -> Result<Vec<PolicyEntry>, PolicyFileError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            parse_policy_line(line).map_err(|error| PolicyFileError { line: i + 1, error })
+        })
+        .collect()
+}
+
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<(Policy, String)> {
+pub fn input_generator(input: &str) -> Result<Vec<(Policy, String)>, PolicyFileError> {
     input
         .lines()
-        .map(|line| {
-            let mut line_iter = line.split(':');
-            let policy = line_iter.next().unwrap();
-            let pwd = line_iter.next().unwrap().trim_start();
-            let policy = Policy::new(policy);
+        .enumerate()
+        .map(|(i, line)| {
+            let error = |error| PolicyFileError { line: i + 1, error };
+            let (policy, pwd) = line
+                .split_once(": ")
+                .ok_or_else(|| error(ParsePolicyError::MissingPassword))?;
+            let policy = policy.parse().map_err(error)?;
 
-            (policy, pwd.to_owned())
+            Ok((policy, pwd.to_owned()))
         })
         .collect()
 }
 
 fn respects_policy_part1(pol: &Policy, pwd: &str) -> bool {
-    let Policy(lower_limit, upper_limit, c) = *pol;
+    pol.count_range().check(pwd) == Ok(true)
+}
+
+/// One database row checked against both readings of its policy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RowAudit {
+    pub line: usize,
+    pub policy: Policy,
+    pub pwd: String,
+    pub occurrences: usize,
+    pub count: bool,
+    pub positional: Result<bool, PolicyError>,
+}
+
+impl RowAudit {
+    pub fn positional_ok(&self) -> bool {
+        self.positional == Ok(true)
+    }
+
+    /// How far the occurrences of the policy character fall outside the
+    /// allowed range: negative below the minimum, positive above the maximum.
+    pub fn range_offset(&self) -> isize {
+        let Policy(min, max, _) = self.policy;
+
+        if self.occurrences < min {
+            self.occurrences as isize - min as isize
+        } else if self.occurrences > max {
+            (self.occurrences - max) as isize
+        } else {
+            0
+        }
+    }
 
-    let count = pwd.chars().filter(|x| *x == c).count();
+    fn positional_str(&self) -> String {
+        match self.positional {
+            Ok(true) => "pass".to_owned(),
+            Ok(false) => "fail".to_owned(),
+            Err(e) => e.to_string(),
+        }
+    }
+}
 
-    count >= lower_limit && count <= upper_limit
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CharStats {
+    pub rows: usize,
+    pub count_failures: usize,
+    pub positional_failures: usize,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AuditReport {
+    pub rows: Vec<RowAudit>,
+}
+
+impl AuditReport {
+    pub fn new(input: &[(Policy, String)]) -> Self {
+        let rows = input
+            .iter()
+            .enumerate()
+            .map(|(i, (policy, pwd))| RowAudit {
+                line: i + 1,
+                policy: *policy,
+                pwd: pwd.to_owned(),
+                occurrences: pwd.chars().filter(|c| *c == policy.2).count(),
+                count: respects_policy_part1(policy, pwd),
+                positional: policy.positional().check(pwd),
+            })
+            .collect();
+
+        Self { rows }
+    }
+
+    pub fn count_failures(&self) -> impl Iterator<Item = &RowAudit> {
+        self.rows.iter().filter(|r| !r.count)
+    }
+
+    pub fn positional_failures(&self) -> impl Iterator<Item = &RowAudit> {
+        self.rows.iter().filter(|r| !r.positional_ok())
+    }
+
+    /// Rows valid under one reading of the policy and invalid under the other.
+    pub fn disagreements(&self) -> impl Iterator<Item = &RowAudit> {
+        self.rows.iter().filter(|r| r.count != r.positional_ok())
+    }
+
+    pub fn by_char(&self) -> BTreeMap<char, CharStats> {
+        let mut stats: BTreeMap<char, CharStats> = BTreeMap::new();
+
+        for r in self.rows.iter() {
+            let s = stats.entry(r.policy.2).or_default();
+            s.rows += 1;
+            s.count_failures += !r.count as usize;
+            s.positional_failures += !r.positional_ok() as usize;
+        }
+
+        stats
+    }
+
+    /// Rows per `RowAudit::range_offset`.
+    pub fn range_offsets(&self) -> BTreeMap<isize, usize> {
+        let mut offsets = BTreeMap::new();
+
+        for r in self.rows.iter() {
+            *offsets.entry(r.range_offset()).or_insert(0) += 1;
+        }
+
+        offsets
+    }
+
+    pub fn to_table(&self) -> String {
+        let lines = |rows: Vec<&RowAudit>| {
+            rows.iter()
+                .map(|r| r.line.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let mut table = format!(
+            "rows: {}  count valid: {}  positional valid: {}  disagreements: {}\n",
+            self.rows.len(),
+            self.rows.len() - self.count_failures().count(),
+            self.rows.len() - self.positional_failures().count(),
+            self.disagreements().count(),
+        );
+
+        table += "\nchar   rows  count fail  positional fail\n";
+        for (c, s) in self.by_char() {
+            table += &format!(
+                "{:<4} {:>6} {:>11} {:>16}\n",
+                c, s.rows, s.count_failures, s.positional_failures
+            );
+        }
+
+        table += "\noccurrences vs range   rows\n";
+        for (offset, cnt) in self.range_offsets() {
+            let label = match offset {
+                0 => "within".to_owned(),
+                x if x < 0 => format!("{} below min", -x),
+                x => format!("{} above max", x),
+            };
+            table += &format!("{:<20} {:>6}\n", label, cnt);
+        }
+
+        table += &format!(
+            "\ncount failures: {}\n",
+            lines(self.count_failures().collect())
+        );
+        table += &format!(
+            "positional failures: {}\n",
+            lines(self.positional_failures().collect())
+        );
+
+        table += "\nline   policy   count  positional  password\n";
+        for r in self.disagreements() {
+            table += &format!(
+                "{:<6} {:<8} {:<6} {:<11} {}\n",
+                r.line,
+                r.policy.to_string(),
+                if r.count { "pass" } else { "fail" },
+                r.positional_str(),
+                r.pwd
+            );
+        }
+
+        table
+    }
+
+    pub fn to_json(&self) -> String {
+        let lines = |rows: Vec<&RowAudit>| {
+            rows.iter()
+                .map(|r| r.line.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let by_char = self
+            .by_char()
+            .iter()
+            .map(|(c, s)| {
+                format!(
+                    "{}:{{\"rows\":{},\"count_failures\":{},\"positional_failures\":{}}}",
+                    json_string(&c.to_string()),
+                    s.rows,
+                    s.count_failures,
+                    s.positional_failures
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        let offsets = self
+            .range_offsets()
+            .iter()
+            .map(|(offset, cnt)| format!("\"{}\":{}", offset, cnt))
+            .collect::<Vec<String>>()
+            .join(",");
+        let disagreements = self
+            .disagreements()
+            .map(|r| {
+                format!(
+                    "{{\"line\":{},\"policy\":{},\"password\":{},\"count\":{},\"positional\":{}}}",
+                    r.line,
+                    json_string(&r.policy.to_string()),
+                    json_string(&r.pwd),
+                    json_string(if r.count { "pass" } else { "fail" }),
+                    json_string(&r.positional_str()),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!(
+            "{{\"rows\":{},\"by_char\":{{{}}},\"range_offsets\":{{{}}},\
+             \"count_failures\":[{}],\"positional_failures\":[{}],\"disagreements\":[{}]}}",
+            self.rows.len(),
+            by_char,
+            offsets,
+            lines(self.count_failures().collect()),
+            lines(self.positional_failures().collect()),
+            disagreements,
+        )
+    }
 }
 
 #[aoc(day2, part1)]
@@ -45,9 +568,7 @@ pub fn part1(input: &[(Policy, String)]) -> usize {
 }
 
 fn respects_policy_part2(pol: &Policy, pwd: &str) -> bool {
-    let Policy(i, j, c) = *pol;
-
-    (pwd.chars().nth(i - 1).unwrap() == c) ^ (pwd.chars().nth(j - 1).unwrap() == c)
+    pol.positional().check(pwd) == Ok(true)
 }
 
 #[aoc(day2, part2)]
@@ -62,19 +583,149 @@ pub fn test1() {
     let pol = Policy(1, 3, 'a');
     let pwd = "abcde";
 
-    assert!(respects_policy_part2(&pol, &pwd));
+    assert!(respects_policy_part2(&pol, pwd));
 }
 #[test]
 pub fn test2() {
     let pol = Policy(1, 3, 'b');
     let pwd = "cdefg";
 
-    assert!(!respects_policy_part2(&pol, &pwd));
+    assert!(!respects_policy_part2(&pol, pwd));
 }
 #[test]
 pub fn test3() {
     let pol = Policy(2, 9, 'c');
     let pwd = "ccccccccc";
 
-    assert!(!respects_policy_part2(&pol, &pwd));
+    assert!(!respects_policy_part2(&pol, pwd));
+}
+
+#[test]
+pub fn test_policy_file() {
+    let policies = parse_policy_file(
+        "count 1-3 a: abcde
+xor 1-3 b: cdefg
+classes lower,digit,symbol: hunter2!
+
+forbid 123,pass: password123
+entropy 8: aaaaaaaaaaaaaaaab
+max-run 2: bookkeeper",
+    )
+    .unwrap();
+    let results: Vec<bool> = policies
+        .iter()
+        .map(|(pol, pwd)| pol.check(pwd).unwrap())
+        .collect();
+
+    assert_eq!(results, vec![true, false, true, false, false, true]);
+    assert_eq!(MinEntropy(8.0).check("abcdefgh"), Ok(true));
+    assert_eq!(MaxRun(1).check("bookkeeper"), Ok(false));
+    assert_eq!(
+        parse_policy_file("count 1-3 a: abc\nsize 8: abc").err(),
+        Some(PolicyFileError {
+            line: 2,
+            error: ParsePolicyError::UnknownKind("size".to_owned())
+        })
+    );
+    assert_eq!(
+        parse_policy_line("classes lower,emoji: abc").err(),
+        Some(ParsePolicyError::InvalidArguments("lower,emoji".to_owned()))
+    );
+}
+
+#[test]
+pub fn test_positions() {
+    let pol = PositionalXor {
+        first: 2,
+        second: 4,
+        c: 'ñ',
+    };
+
+    assert_eq!(pol.check("añañ"), Ok(false));
+    assert_eq!(pol.check("añab"), Ok(true));
+    assert_eq!(
+        pol.check("añ"),
+        Err(PolicyError::PositionOutOfRange {
+            position: 4,
+            len: 2
+        })
+    );
+    assert_eq!(
+        PositionalXor { first: 0, ..pol }.check("añab"),
+        Err(PolicyError::PositionOutOfRange {
+            position: 0,
+            len: 4
+        })
+    );
+}
+
+#[test]
+pub fn test_policy_from_str() {
+    assert_eq!(
+        "2-9 c".parse::<Policy>().map(|p| p.count_range()),
+        Ok(CountRange {
+            min: 2,
+            max: 9,
+            c: 'c'
+        })
+    );
+    assert_eq!(
+        "2-x c".parse::<Policy>().err(),
+        Some(ParsePolicyError::InvalidRange("2-x".to_owned()))
+    );
+    assert_eq!(
+        "1-3 ab".parse::<Policy>().err(),
+        Some(ParsePolicyError::InvalidChar("ab".to_owned()))
+    );
+    assert_eq!(
+        "1-3".parse::<Policy>().err(),
+        Some(ParsePolicyError::InvalidArguments("1-3".to_owned()))
+    );
+    assert_eq!(
+        input_generator("1-3 a: abcde\n1-3 b cdefg").err(),
+        Some(PolicyFileError {
+            line: 2,
+            error: ParsePolicyError::MissingPassword
+        })
+    );
+}
+
+#[test]
+pub fn test_audit() {
+    let input = input_generator("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n3-4 d: dd").unwrap();
+    let report = AuditReport::new(&input);
+
+    assert_eq!(
+        report.count_failures().map(|r| r.line).collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+    assert_eq!(
+        report
+            .positional_failures()
+            .map(|r| r.line)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    assert_eq!(
+        report.disagreements().map(|r| r.line).collect::<Vec<_>>(),
+        vec![3]
+    );
+    assert_eq!(
+        report.by_char().get(&'c'),
+        Some(&CharStats {
+            rows: 1,
+            count_failures: 0,
+            positional_failures: 1
+        })
+    );
+    assert_eq!(
+        report.range_offsets().into_iter().collect::<Vec<_>>(),
+        vec![(-1, 2), (0, 2)]
+    );
+    assert!(report.to_json().contains(
+        "\"disagreements\":[{\"line\":3,\"policy\":\"2-9 c\",\"password\":\"ccccccccc\",\"count\":\"pass\",\"positional\":\"fail\"}]"
+    ));
+    assert!(report
+        .to_table()
+        .contains("rows: 4  count valid: 2  positional valid: 1  disagreements: 1"));
 }
@@ -1,133 +1,494 @@
-use std::ops::Index;
+use crate::grid::{Addressing, Edge, Grid, ParseGridError, Pos};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::RangeInclusive;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Cell {
     Path,
     Tree,
 }
 
-pub struct Map {
-    pub width: usize,
-    pub height: usize,
-    pub data: Vec<Cell>,
+impl Cell {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Cell::Path),
+            '#' => Some(Cell::Tree),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match *self {
+            Cell::Path => '.',
+            Cell::Tree => '#',
+        };
+
+        write!(f, "{}", c)
+    }
+}
+
+pub type Map = Grid<Cell>;
+
+/// What the toboggan does at the left and right edges of the map. Every
+/// traversal ends past the bottom row.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Traversal {
+    Wrap,
+    StopAtEdge,
+    Reflect,
+}
+
+impl Traversal {
+    fn addressing(self) -> Addressing {
+        let x = match self {
+            Traversal::Wrap => Edge::Wrap,
+            Traversal::StopAtEdge => Edge::Stop,
+            Traversal::Reflect => Edge::Reflect,
+        };
+
+        Addressing::new(x, Edge::Stop)
+    }
+}
+
+/// Moves `right / right_den` columns (rounded down) and `down` rows per step.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Slope {
+    right: isize,
+    right_den: usize,
+    down: usize,
 }
 
-impl Map {
-    pub fn new(w: usize, h: usize, data: Vec<Cell>) -> Self {
+impl Slope {
+    pub const fn new(right: isize, down: usize) -> Self {
+        assert!(down > 0, "a slope has to move down");
+
         Self {
-            width: w,
-            height: h,
-            data,
+            right,
+            right_den: 1,
+            down,
         }
     }
 
-    pub fn map_index(&self, pos: Pos) -> usize {
-        let (x, y) = pos;
+    pub fn fractional(right: isize, right_den: usize, down: usize) -> Self {
+        assert!(right_den > 0, "the denominator can't be zero");
+        let gcd = gcd(right.unsigned_abs(), right_den);
 
-        if y >= self.height {
-            panic!("Woo, u should have stopped this earlier");
-        } else {
-            y * self.width + x % self.width
+        Self {
+            right: right / gcd as isize,
+            right_den: right_den / gcd,
+            ..Self::new(0, down)
         }
     }
-}
-
-type Pos = (usize, usize);
 
-impl Index<Pos> for Map {
-    type Output = Cell;
+    fn pos_at(&self, step: isize) -> Pos {
+        (
+            (step * self.right).div_euclid(self.right_den as isize),
+            step * self.down as isize,
+        )
+    }
+}
 
-    fn index(&self, index: Pos) -> &Self::Output {
-        let i = self.map_index(index);
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.right_den == 1 {
+            write!(f, "right {}, down {}", self.right, self.down)
+        } else {
+            write!(
+                f,
+                "right {}/{}, down {}",
+                self.right, self.right_den, self.down
+            )
+        }
+    }
+}
 
-        &self.data[i]
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
 }
 
+/// Positions on the map visited along `slope`, ending at the first step
+/// that leaves the map under `traversal`.
 #[derive(Clone, Copy)]
-pub struct Line {
-    x: usize,
-    y: usize,
-    x_inc: usize,
-    y_inc: usize,
+pub struct Line<'a> {
+    map: &'a Map,
+    slope: Slope,
+    addressing: Addressing,
+    step: isize,
 }
 
-impl Line {
-    pub fn new(x_inc: usize, y_inc: usize) -> Self {
+impl<'a> Line<'a> {
+    pub fn new(map: &'a Map, slope: Slope, traversal: Traversal) -> Self {
         Self {
-            x: 0,
-            y: 0,
-            x_inc,
-            y_inc,
+            map,
+            slope,
+            addressing: traversal.addressing(),
+            step: 0,
         }
     }
 }
 
-impl Iterator for Line {
+impl Iterator for Line<'_> {
     type Item = Pos;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.x += self.x_inc;
-        self.y += self.y_inc;
+        self.step += 1;
+        let pos = self
+            .map
+            .resolve(self.slope.pos_at(self.step), self.addressing);
+
+        if pos.is_none() {
+            self.step -= 1;
+        }
 
-        Some((self.x, self.y))
+        pos
     }
 }
 
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Map {
-    let width = input.find('\n').unwrap();
-    let mut height: usize = 0;
-    let mut data: Vec<Cell> = vec![];
-
-    for line in input.lines() {
-        height += 1;
-
-        let mut row: Vec<Cell> = line
-            .chars()
-            .map(|c| if c == '.' { Cell::Path } else { Cell::Tree })
-            .collect();
-        data.append(row.as_mut());
+pub fn input_generator(input: &str) -> Result<Map, ParseGridError> {
+    Grid::parse(input, Cell::from_char)
+}
+
+fn trees_on_path(map: &Map, slope: Slope, traversal: Traversal) -> usize {
+    Line::new(map, slope, traversal)
+        .filter(|pos| map[*pos] == Cell::Tree)
+        .count()
+}
+
+pub fn evaluate_slopes(map: &Map, slopes: &[Slope], traversal: Traversal) -> Vec<(Slope, usize)> {
+    slopes
+        .iter()
+        .map(|slope| (*slope, trees_on_path(map, *slope, traversal)))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Objective {
+    FewestTrees,
+    MostTrees,
+}
+
+/// Candidate slopes for `rank_slopes`: every `right / right_den` inside
+/// `right` with a denominator up to `max_den`, for each `down` step.
+#[derive(Clone, Debug)]
+pub struct SlopeBounds {
+    pub right: RangeInclusive<isize>,
+    pub down: RangeInclusive<usize>,
+    pub max_den: usize,
+}
+
+impl SlopeBounds {
+    fn candidates(&self) -> impl Iterator<Item = Slope> + '_ {
+        self.down
+            .clone()
+            .filter(|down| *down > 0)
+            .flat_map(move |down| {
+                (1..=self.max_den).flat_map(move |den| {
+                    let lo = self.right.start() * den as isize;
+                    let hi = self.right.end() * den as isize;
+
+                    (lo..=hi)
+                        .filter(move |right| gcd(right.unsigned_abs(), den) == 1)
+                        .map(move |right| Slope::fractional(right, den, down))
+                })
+            })
+    }
+}
+
+/// Every slope within `bounds` with its tree count, best first.
+pub fn rank_slopes(
+    map: &Map,
+    bounds: &SlopeBounds,
+    objective: Objective,
+    traversal: Traversal,
+) -> Vec<(Slope, usize)> {
+    let candidates = bounds.candidates().collect::<Vec<Slope>>();
+    let mut ranking = evaluate_slopes(map, &candidates, traversal);
+
+    match objective {
+        Objective::FewestTrees => ranking.sort_by_key(|(_, trees)| *trees),
+        Objective::MostTrees => ranking.sort_by_key(|(_, trees)| Reverse(*trees)),
     }
 
-    Map::new(width, height, data)
+    ranking
 }
 
-fn trees_on_path(map: &Map, path: &Line) -> usize {
-    let mut trees: usize = 0;
+pub fn find_best_slope(
+    map: &Map,
+    bounds: &SlopeBounds,
+    objective: Objective,
+    traversal: Traversal,
+) -> Option<(Slope, usize)> {
+    rank_slopes(map, bounds, objective, traversal)
+        .into_iter()
+        .next()
+}
+
+/// Draws the map with every visited cell marked `O` if open and `X` if a
+/// tree. When wrapping, the map is repeated sideways as far as the path goes.
+pub fn render_path(map: &Map, slope: Slope, traversal: Traversal) -> String {
+    let steps = Line::new(map, slope, traversal).count() as isize;
+    let width = map.width() as isize;
+
+    let (path, columns): (HashSet<Pos>, _) = match traversal {
+        Traversal::Wrap => {
+            let path: HashSet<Pos> = (1..=steps).map(|step| slope.pos_at(step)).collect();
+            let min_x = path.iter().map(|(x, _)| *x).min().unwrap_or(0).min(0);
+            let max_x = path.iter().map(|(x, _)| *x).max().unwrap_or(0);
+            let first_tile = min_x.div_euclid(width);
+            let last_tile = max_x.div_euclid(width);
 
-    for (x, y) in path.into_iter() {
-        if y >= map.height {
-            break;
+            (path, first_tile * width..(last_tile + 1) * width)
         }
+        _ => (Line::new(map, slope, traversal).collect(), 0..width),
+    };
+
+    (0..map.height() as isize)
+        .map(|y| {
+            columns
+                .clone()
+                .map(|x| {
+                    let cell = map[(x.rem_euclid(width), y)];
+
+                    match cell {
+                        _ if !path.contains(&(x, y)) => cell.to_string(),
+                        Cell::Path => "O".to_owned(),
+                        Cell::Tree => "X".to_owned(),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeastTreesPath {
+    pub trees: usize,
+    pub path: Vec<Pos>,
+}
+
+/// Path from the top-left corner to the bottom row hitting the fewest trees,
+/// where every step goes down one row and up to `max_shift` columns sideways.
+/// Of the equally good ways into a cell, the one with the smallest sideways
+/// shift wins. `path` starts at `(0, 0)` and holds the
+/// on-map position of every row, resolved as `traversal` dictates.
+pub fn least_trees_path(map: &Map, max_shift: usize, traversal: Traversal) -> LeastTreesPath {
+    let addressing = traversal.addressing();
+    let width = map.width();
+    let shift = max_shift as isize;
+    let mut shifts: Vec<isize> = (-shift..=shift).collect();
+    shifts.sort_by_key(|d| d.abs());
+
+    let mut trees: Vec<Option<usize>> = vec![None; width];
+    trees[0] = Some(0);
+    let mut came_from: Vec<Vec<usize>> = vec![];
+
+    for y in 1..map.height() as isize {
+        // Best (trees, shift size) found so far into every column of row y
+        let mut next: Vec<Option<(usize, isize)>> = vec![None; width];
+        let mut from = vec![0; width];
 
-        trees += match map[(x, y)] {
-            Cell::Tree => 1,
-            Cell::Path => 0,
+        for (x, cnt) in trees.iter().enumerate() {
+            let cnt = match cnt {
+                Some(cnt) => *cnt,
+                None => continue,
+            };
+
+            for d in shifts.iter() {
+                if let Some(pos) = map.resolve((x as isize + d, y), addressing) {
+                    let key = (cnt + (map[pos] == Cell::Tree) as usize, d.abs());
+                    let nx = pos.0 as usize;
+
+                    if next[nx].is_none_or(|best| key < best) {
+                        next[nx] = Some(key);
+                        from[nx] = x;
+                    }
+                }
+            }
         }
+
+        trees = next.iter().map(|best| best.map(|(cnt, _)| cnt)).collect();
+        came_from.push(from);
     }
 
-    trees
+    let (mut x, least) = trees
+        .iter()
+        .enumerate()
+        .filter_map(|(x, cnt)| cnt.map(|cnt| (x, cnt)))
+        .min_by_key(|(_, cnt)| *cnt)
+        .unwrap();
+
+    let mut path = vec![];
+    for (y, from) in came_from.iter().enumerate().rev() {
+        path.push((x as isize, y as isize + 1));
+        x = from[x];
+    }
+    path.push((0, 0));
+    path.reverse();
+
+    LeastTreesPath { trees: least, path }
 }
 
+const PART2_SLOPES: [Slope; 5] = [
+    Slope::new(1, 1),
+    Slope::new(3, 1),
+    Slope::new(5, 1),
+    Slope::new(7, 1),
+    Slope::new(1, 2),
+];
+
 #[aoc(day3, part1)]
 pub fn part1(input: &Map) -> usize {
-    let path = Line::new(3, 1);
-
-    trees_on_path(input, &path)
+    trees_on_path(input, Slope::new(3, 1), Traversal::Wrap)
 }
 
 #[aoc(day3, part2)]
 pub fn part2(input: &Map) -> usize {
-    let paths = &[
-        Line::new(1, 1),
-        Line::new(3, 1),
-        Line::new(5, 1),
-        Line::new(7, 1),
-        Line::new(1, 2),
-    ];
-
-    paths
+    evaluate_slopes(input, &PART2_SLOPES, Traversal::Wrap)
         .iter()
-        .fold(1, |acc, path| acc * trees_on_path(input, path))
+        .map(|(_, trees)| trees)
+        .product()
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+#[test]
+pub fn test_example() {
+    let map = input_generator(EXAMPLE).unwrap();
+
+    assert_eq!(part1(&map), 7);
+    assert_eq!(part2(&map), 336);
+}
+
+#[test]
+pub fn test_fractional_slopes() {
+    let map = input_generator(EXAMPLE).unwrap();
+    let half = Slope::fractional(1, 2, 1);
+
+    assert_eq!(Slope::fractional(-4, 2, 1), Slope::new(-2, 1));
+    assert_eq!(half.to_string(), "right 1/2, down 1");
+    assert_eq!(
+        Line::new(&map, half, Traversal::Wrap)
+            .take(4)
+            .collect::<Vec<Pos>>(),
+        vec![(0, 1), (1, 2), (1, 3), (2, 4)]
+    );
+    assert_eq!(
+        evaluate_slopes(
+            &map,
+            &[Slope::new(3, 1), Slope::new(-3, 1)],
+            Traversal::Wrap
+        ),
+        vec![(Slope::new(3, 1), 7), (Slope::new(-3, 1), 3)]
+    );
+}
+
+#[test]
+pub fn test_rank_slopes() {
+    let map = input_generator(EXAMPLE).unwrap();
+    let bounds = SlopeBounds {
+        right: -3..=3,
+        down: 1..=2,
+        max_den: 2,
+    };
+    let ranking = rank_slopes(&map, &bounds, Objective::MostTrees, Traversal::Wrap);
+
+    assert_eq!(ranking.len(), 2 * (7 + 6));
+    assert!(ranking.windows(2).all(|w| w[0].1 >= w[1].1));
+    assert_eq!(
+        find_best_slope(&map, &bounds, Objective::FewestTrees, Traversal::Wrap)
+            .map(|(_, trees)| trees),
+        ranking.last().map(|(_, trees)| *trees)
+    );
+}
+
+#[test]
+pub fn test_traversal_modes() {
+    let map = input_generator(EXAMPLE).unwrap();
+    let slope = Slope::new(3, 1);
+
+    assert_eq!(Line::new(&map, slope, Traversal::Wrap).count(), 10);
+    assert_eq!(Line::new(&map, slope, Traversal::StopAtEdge).count(), 3);
+    assert_eq!(
+        Line::new(&map, slope, Traversal::Reflect).last(),
+        Some((8, 10))
+    );
+}
+
+#[test]
+pub fn test_render_path() {
+    let map = input_generator(EXAMPLE).unwrap();
+    let expected = "..##.........##.........##.......
+#..O#...#..#...#...#..#...#...#..
+.#....X..#..#....#..#..#....#..#.
+..#.#...#O#..#.#...#.#..#.#...#.#
+.#...##..#..X...##..#..#...##..#.
+..#.##.......#.X#.......#.##.....
+.#.#.#....#.#.#.#.O..#.#.#.#....#
+.#........#.#........X.#........#
+#.##...#...#.##...#...#.X#...#...
+#...##....##...##....##...#X....#
+.#..#...#.#.#..#...#.#.#..#...X.#";
+
+    assert_eq!(
+        render_path(&map, Slope::new(3, 1), Traversal::Wrap),
+        expected
+    );
+    assert_eq!(
+        render_path(&map, Slope::new(3, 1), Traversal::StopAtEdge)
+            .lines()
+            .nth(3),
+        Some("..#.#...#O#")
+    );
+}
+
+#[test]
+pub fn test_least_trees_path() {
+    let map = input_generator(EXAMPLE).unwrap();
+    let straight = least_trees_path(&map, 0, Traversal::Wrap);
+
+    assert_eq!(straight.trees, 3);
+    assert!(straight.path.iter().all(|(x, _)| *x == 0));
+
+    let best = least_trees_path(&map, 1, Traversal::Wrap);
+    let hit = best.path[1..]
+        .iter()
+        .filter(|pos| map[**pos] == Cell::Tree)
+        .count();
+
+    assert!(best.trees < straight.trees);
+    assert_eq!(best.trees, hit);
+    assert_eq!(best.path.len(), map.height());
+    assert!(best.path.windows(2).all(|w| {
+        let dx = (w[1].0 - w[0].0).rem_euclid(map.width() as isize);
+        w[1].1 == w[0].1 + 1 && (dx <= 1 || dx == map.width() as isize - 1)
+    }));
+
+    // (2, 2) is as good from (0, 1) as from (2, 1), but staying put is the
+    // smaller shift
+    let map = input_generator("...\n...\n##.").unwrap();
+    assert_eq!(
+        least_trees_path(&map, 2, Traversal::StopAtEdge).path,
+        vec![(0, 0), (2, 1), (2, 2)]
+    );
 }
@@ -1,7 +1,20 @@
-use std::collections::HashMap;
+use crate::export::{csv_field, json_string};
+use crate::records::records;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+lazy_static! {
+    static ref DEFAULT_SCHEMA: Schema = include_str!("day4_schema.txt").parse().unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum Field {
     Byr,
     Iyr,
@@ -31,131 +44,899 @@ impl FromStr for Field {
     }
 }
 
-fn parse_passport(input: &str) -> HashMap<Field, String> {
-    input
-        .split_ascii_whitespace()
-        .map(|entry| {
-            let mut splitted = entry.split(':');
-            let key = splitted.next().unwrap();
-            let value = splitted.next().unwrap();
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match *self {
+            Field::Byr => "byr",
+            Field::Iyr => "iyr",
+            Field::Eyr => "eyr",
+            Field::Hgt => "hgt",
+            Field::Hcl => "hcl",
+            Field::Ecl => "ecl",
+            Field::Pid => "pid",
+            Field::Cid => "cid",
+        };
 
-            (key, value)
-        })
-        .map(|(field_str, value)| (Field::from_str(field_str).unwrap(), value.to_owned()))
-        .collect()
+        write!(f, "{}", key)
+    }
 }
 
-#[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> Vec<HashMap<Field, String>> {
-    input.split("\n\n").map(parse_passport).collect()
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rule {
+    Any,
+    Int(RangeInclusive<u32>),
+    Unit(Vec<(String, RangeInclusive<u32>)>),
+    HexColour,
+    OneOf(Vec<String>),
+    Digits(usize),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum Reason {
+    NotANumber,
+    BelowMin(u32),
+    AboveMax(u32),
+    UnknownUnit,
+    NotHexColour,
+    NotAllowed,
+    NotDigits(usize),
 }
 
-fn is_valid_passport1(pass: &HashMap<Field, String>) -> bool {
-    use Field::*;
-    for field in [Byr, Iyr, Eyr, Hgt, Hcl, Ecl, Pid].iter() {
-        if pass.get(field).is_none() {
-            return false;
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Reason::NotANumber => write!(f, "not a number"),
+            Reason::BelowMin(min) => write!(f, "below min {}", min),
+            Reason::AboveMax(max) => write!(f, "above max {}", max),
+            Reason::UnknownUnit => write!(f, "missing or unknown unit"),
+            Reason::NotHexColour => write!(f, "not a hex colour"),
+            Reason::NotAllowed => write!(f, "not an allowed value"),
+            Reason::NotDigits(n) => write!(f, "not {} digits", n),
         }
     }
+}
+
+fn check_range(value: &str, range: &RangeInclusive<u32>) -> Result<(), Reason> {
+    let x = value.parse::<u32>().map_err(|_| Reason::NotANumber)?;
+
+    if x < *range.start() {
+        Err(Reason::BelowMin(*range.start()))
+    } else if x > *range.end() {
+        Err(Reason::AboveMax(*range.end()))
+    } else {
+        Ok(())
+    }
+}
 
-    true
+impl Rule {
+    pub fn validate(&self, value: &str) -> Result<(), Reason> {
+        match self {
+            Rule::Any => Ok(()),
+            Rule::Int(range) => check_range(value, range),
+            Rule::Unit(units) => units
+                .iter()
+                .find_map(|(unit, range)| {
+                    value
+                        .strip_suffix(unit.as_str())
+                        .map(|x| check_range(x, range))
+                })
+                .unwrap_or(Err(Reason::UnknownUnit)),
+            Rule::HexColour => {
+                let is_hex = value.len() == 7
+                    && value.starts_with('#')
+                    && value[1..]
+                        .chars()
+                        .all(|c| matches!(c, '0'..='9' | 'a'..='f'));
+
+                if is_hex {
+                    Ok(())
+                } else {
+                    Err(Reason::NotHexColour)
+                }
+            }
+            Rule::OneOf(values) if values.iter().any(|x| x == value) => Ok(()),
+            Rule::OneOf(_) => Err(Reason::NotAllowed),
+            Rule::Digits(n) if value.len() == *n && value.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(())
+            }
+            Rule::Digits(n) => Err(Reason::NotDigits(*n)),
+        }
+    }
 }
 
-#[aoc(day4, part1)]
-pub fn part1(input: &[HashMap<Field, String>]) -> usize {
-    input
-        .iter()
-        .fold(0, |acc, pass| acc + is_valid_passport1(pass) as usize)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldRule {
+    pub field: Field,
+    pub required: bool,
+    pub rule: Rule,
 }
 
-fn validate_byr(byr: &str) -> bool {
-    if let Ok(value) = byr.parse::<u32>() {
-        value >= 1920 && value <= 2002
-    } else {
-        false
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseSchemaError {
+    UnknownField { line: usize, key: String },
+    InvalidRequirement { line: usize, word: String },
+    UnknownRule { line: usize, rule: String },
+    InvalidArgument { line: usize, arg: String },
+    MissingArgument { line: usize },
+}
+
+impl fmt::Display for ParseSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSchemaError::UnknownField { line, key } => {
+                write!(f, "line {}: unknown field {:?}", line, key)
+            }
+            ParseSchemaError::InvalidRequirement { line, word } => write!(
+                f,
+                "line {}: expected \"required\" or \"optional\", found {:?}",
+                line, word
+            ),
+            ParseSchemaError::UnknownRule { line, rule } => {
+                write!(f, "line {}: unknown rule {:?}", line, rule)
+            }
+            ParseSchemaError::InvalidArgument { line, arg } => {
+                write!(f, "line {}: invalid argument {:?}", line, arg)
+            }
+            ParseSchemaError::MissingArgument { line } => {
+                write!(f, "line {}: missing rule argument", line)
+            }
+        }
     }
 }
 
-fn validate_iyr(iyr: &str) -> bool {
-    if let Ok(value) = iyr.parse::<u32>() {
-        value >= 2010 && value <= 2020
-    } else {
-        false
+impl Error for ParseSchemaError {}
+
+/// Validation rules per field, one per line as
+/// `<field> <required|optional> <rule> [arguments]`; see `day4_schema.txt`
+/// for the rule kinds. Blank lines and lines starting with `#` are skipped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schema {
+    pub rules: Vec<FieldRule>,
+}
+
+impl Schema {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    pub fn validate(&self, pass: &Passport) -> ValidationReport {
+        let mut report = ValidationReport {
+            duplicates: pass.duplicates.iter().map(|(field, _)| *field).collect(),
+            unknown: pass.unknown.iter().map(|(key, _)| key.to_owned()).collect(),
+            malformed: pass.malformed.clone(),
+            ..Default::default()
+        };
+
+        for r in self.rules.iter() {
+            match pass.fields.get(&r.field) {
+                None if r.required => report.missing.push(r.field),
+                None => (),
+                Some(value) => {
+                    if let Err(reason) = r.rule.validate(value) {
+                        report.invalid.push(InvalidField {
+                            field: r.field,
+                            value: value.to_owned(),
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+
+        report
     }
 }
 
-fn validate_eyr(eyr: &str) -> bool {
-    if let Ok(value) = eyr.parse::<u32>() {
-        value >= 2020 && value <= 2030
-    } else {
-        false
+impl Default for Schema {
+    fn default() -> Self {
+        DEFAULT_SCHEMA.clone()
     }
 }
 
-fn validate_hgt(hgt: &str) -> bool {
-    let (value, unit) = hgt.split_at(hgt.len() - 2);
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidField {
+    pub field: Field,
+    pub value: String,
+    pub reason: Reason,
+}
 
-    match value.parse::<u32>() {
-        Ok(x) if unit == "cm" => x >= 150 && x <= 193,
-        Ok(x) if unit == "in" => x >= 59 && x <= 76,
-        _ => false,
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} {}", self.field, self.value, self.reason)
     }
 }
 
-fn validate_hcl(hcl: &str) -> bool {
-    if hcl.len() == 7 {
-        hcl.chars().skip(1).fold(true, |acc, c| match c {
-            '0'..='9' | 'a'..='f' => acc & true,
-            _ => false,
-        })
-    } else {
-        false
+/// Duplicated fields and malformed entries make a passport invalid, while
+/// unknown keys are only reported.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+    pub missing: Vec<Field>,
+    pub invalid: Vec<InvalidField>,
+    pub duplicates: Vec<Field>,
+    pub unknown: Vec<String>,
+    pub malformed: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn has_required_fields(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty()
+            && self.invalid.is_empty()
+            && self.duplicates.is_empty()
+            && self.malformed.is_empty()
     }
 }
 
-fn validate_ecl(ecl: &str) -> bool {
-    match ecl {
-        "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth" => true,
-        _ => false,
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing = self
+            .missing
+            .iter()
+            .map(|field| format!("{}: missing", field));
+        let invalid = self.invalid.iter().map(InvalidField::to_string);
+        let duplicates = self
+            .duplicates
+            .iter()
+            .map(|field| format!("{}: duplicated", field));
+        let unknown = self
+            .unknown
+            .iter()
+            .map(|key| format!("{}: unknown field", key));
+        let malformed = self
+            .malformed
+            .iter()
+            .map(|entry| format!("{:?}: malformed entry", entry));
+        let lines = missing
+            .chain(invalid)
+            .chain(duplicates)
+            .chain(unknown)
+            .chain(malformed)
+            .collect::<Vec<_>>();
+
+        if lines.is_empty() {
+            write!(f, "valid")
+        } else {
+            write!(f, "{}", lines.join("\n"))
+        }
     }
 }
 
-fn validate_pid(pid: &str) -> bool {
-    pid.len() == 9 && pid.parse::<u32>().is_ok()
+/// Failure counts over a batch of passports.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationSummary {
+    pub passports: usize,
+    pub valid: usize,
+    pub missing: BTreeMap<Field, usize>,
+    pub invalid: BTreeMap<(Field, Reason), usize>,
+    pub duplicates: BTreeMap<Field, usize>,
+    pub unknown: BTreeMap<String, usize>,
+    pub malformed: usize,
+}
+
+impl<'a> FromIterator<&'a ValidationReport> for ValidationSummary {
+    fn from_iter<I: IntoIterator<Item = &'a ValidationReport>>(reports: I) -> Self {
+        let mut summary = ValidationSummary::default();
+
+        for report in reports {
+            summary.passports += 1;
+            summary.valid += report.is_valid() as usize;
+
+            for field in report.missing.iter() {
+                *summary.missing.entry(*field).or_insert(0) += 1;
+            }
+            for invalid in report.invalid.iter() {
+                *summary
+                    .invalid
+                    .entry((invalid.field, invalid.reason))
+                    .or_insert(0) += 1;
+            }
+            for field in report.duplicates.iter() {
+                *summary.duplicates.entry(*field).or_insert(0) += 1;
+            }
+            for key in report.unknown.iter() {
+                *summary.unknown.entry(key.to_owned()).or_insert(0) += 1;
+            }
+            summary.malformed += report.malformed.len();
+        }
+
+        summary
+    }
 }
 
-fn validate_field(field: Field, val: &str) -> bool {
-    use Field::*;
-    match field {
-        Byr => validate_byr(val),
-        Iyr => validate_iyr(val),
-        Eyr => validate_eyr(val),
-        Hgt => validate_hgt(val),
-        Hcl => validate_hcl(val),
-        Ecl => validate_ecl(val),
-        Pid => validate_pid(val),
-        Cid => true,
+impl fmt::Display for ValidationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} of {} passports valid", self.valid, self.passports)?;
+        for (field, cnt) in self.missing.iter() {
+            writeln!(f, "{:>6}  {}: missing", cnt, field)?;
+        }
+        for ((field, reason), cnt) in self.invalid.iter() {
+            writeln!(f, "{:>6}  {}: {}", cnt, field, reason)?;
+        }
+        for (field, cnt) in self.duplicates.iter() {
+            writeln!(f, "{:>6}  {}: duplicated", cnt, field)?;
+        }
+        for (key, cnt) in self.unknown.iter() {
+            writeln!(f, "{:>6}  {}: unknown field", cnt, key)?;
+        }
+        if self.malformed > 0 {
+            writeln!(f, "{:>6}  malformed entries", self.malformed)?;
+        }
+
+        Ok(())
     }
 }
 
-fn is_valid_passport2(pass: &HashMap<Field, String>) -> bool {
-    use Field::*;
-    for field in [Byr, Iyr, Eyr, Hgt, Hcl, Ecl, Pid].iter() {
-        if let Some(value) = pass.get(field) {
-            if !validate_field(*field, value) {
-                return false;
+fn parse_range(line: usize, arg: &str) -> Result<RangeInclusive<u32>, ParseSchemaError> {
+    let invalid = || ParseSchemaError::InvalidArgument {
+        line,
+        arg: arg.to_owned(),
+    };
+    let mut bounds = arg.split("..");
+    let min = bounds
+        .next()
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(invalid)?;
+    let max = bounds
+        .next()
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(invalid)?;
+
+    if bounds.next().is_some() || min > max {
+        Err(invalid())
+    } else {
+        Ok(min..=max)
+    }
+}
+
+fn parse_rule(line: usize, words: &[&str]) -> Result<FieldRule, ParseSchemaError> {
+    let field = Field::from_str(words[0]).map_err(|_| ParseSchemaError::UnknownField {
+        line,
+        key: words[0].to_owned(),
+    })?;
+    let required = match words.get(1) {
+        Some(&"required") => true,
+        Some(&"optional") => false,
+        Some(word) => {
+            return Err(ParseSchemaError::InvalidRequirement {
+                line,
+                word: (*word).to_owned(),
+            })
+        }
+        None => return Err(ParseSchemaError::MissingArgument { line }),
+    };
+    let kind = words
+        .get(2)
+        .ok_or(ParseSchemaError::MissingArgument { line })?;
+    let args = &words[3..];
+    let arg = |i: usize| {
+        args.get(i)
+            .ok_or(ParseSchemaError::MissingArgument { line })
+    };
+
+    let rule = match *kind {
+        "any" => Rule::Any,
+        "int" => Rule::Int(parse_range(line, arg(0)?)?),
+        "unit" if !args.is_empty() && args.len().is_multiple_of(2) => Rule::Unit(
+            args.chunks(2)
+                .map(|unit| Ok((unit[0].to_owned(), parse_range(line, unit[1])?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        "unit" => return Err(ParseSchemaError::MissingArgument { line }),
+        "hex-colour" => Rule::HexColour,
+        "one-of" if !args.is_empty() => Rule::OneOf(args.iter().map(|x| (*x).to_owned()).collect()),
+        "one-of" => return Err(ParseSchemaError::MissingArgument { line }),
+        "digits" => {
+            Rule::Digits(
+                arg(0)?
+                    .parse()
+                    .map_err(|_| ParseSchemaError::InvalidArgument {
+                        line,
+                        arg: args[0].to_owned(),
+                    })?,
+            )
+        }
+        _ => {
+            return Err(ParseSchemaError::UnknownRule {
+                line,
+                rule: (*kind).to_owned(),
+            })
+        }
+    };
+
+    Ok(FieldRule {
+        field,
+        required,
+        rule,
+    })
+}
+
+impl FromStr for Schema {
+    type Err = ParseSchemaError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let rules = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split_whitespace().collect::<Vec<&str>>()))
+            .filter(|(_, words)| !words.is_empty() && !words[0].starts_with('#'))
+            .map(|(line, words)| parse_rule(line, &words))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Schema { rules })
+    }
+}
+
+/// A passport as written, keeping the first value of every known field and
+/// recording whatever else was found so validation can report it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Passport {
+    pub fields: HashMap<Field, String>,
+    pub duplicates: Vec<(Field, String)>,
+    pub unknown: Vec<(String, String)>,
+    pub malformed: Vec<String>,
+}
+
+impl Passport {
+    pub fn parse<'a, I: IntoIterator<Item = &'a str>>(entries: I) -> Self {
+        let mut pass = Passport::default();
+
+        for entry in entries {
+            let (key, value) = match entry.split_once(':') {
+                Some(kv) => kv,
+                None => {
+                    pass.malformed.push(entry.to_owned());
+                    continue;
+                }
+            };
+
+            match Field::from_str(key) {
+                Ok(field) if pass.fields.contains_key(&field) => {
+                    pass.duplicates.push((field, value.to_owned()))
+                }
+                Ok(field) => {
+                    pass.fields.insert(field, value.to_owned());
+                }
+                Err(_) => pass.unknown.push((key.to_owned(), value.to_owned())),
             }
-        } else {
-            return false;
         }
+
+        pass
     }
+}
 
-    true
+#[aoc_generator(day4)]
+pub fn input_generator(input: &str) -> Vec<Passport> {
+    records(input)
+        .map(|lines| Passport::parse(lines.iter().flat_map(|line| line.split_whitespace())))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Height {
+    Cm(u32),
+    In(u32),
+}
+
+impl Height {
+    fn parts(self) -> (u32, &'static str) {
+        match self {
+            Height::Cm(x) => (x, "cm"),
+            Height::In(x) => (x, "in"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EyeColour {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+impl FromStr for EyeColour {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<EyeColour, Self::Err> {
+        match input {
+            "amb" => Ok(EyeColour::Amb),
+            "blu" => Ok(EyeColour::Blu),
+            "brn" => Ok(EyeColour::Brn),
+            "gry" => Ok(EyeColour::Gry),
+            "grn" => Ok(EyeColour::Grn),
+            "hzl" => Ok(EyeColour::Hzl),
+            "oth" => Ok(EyeColour::Oth),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for EyeColour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            EyeColour::Amb => "amb",
+            EyeColour::Blu => "blu",
+            EyeColour::Brn => "brn",
+            EyeColour::Gry => "gry",
+            EyeColour::Grn => "grn",
+            EyeColour::Hzl => "hzl",
+            EyeColour::Oth => "oth",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A passport with every field parsed into its own type. Building one only
+/// checks that the values can be typed, so validate against a `Schema` first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassportRecord {
+    pub birth_year: u32,
+    pub issue_year: u32,
+    pub expiration_year: u32,
+    pub height: Height,
+    pub hair_colour: Rgb,
+    pub eye_colour: EyeColour,
+    pub passport_id: String,
+    pub country_id: Option<String>,
+}
+
+/// The field a `PassportRecord` couldn't be built from, with its value if it
+/// was there at all.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NormalizeError {
+    pub field: Field,
+    pub value: Option<String>,
+}
+
+impl fmt::Display for NormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: can't normalize {:?}", self.field, value),
+            None => write!(f, "{}: missing", self.field),
+        }
+    }
+}
+
+impl Error for NormalizeError {}
+
+impl TryFrom<&Passport> for PassportRecord {
+    type Error = NormalizeError;
+
+    fn try_from(pass: &Passport) -> Result<Self, Self::Error> {
+        fn typed<T, F>(pass: &Passport, field: Field, parse: F) -> Result<T, NormalizeError>
+        where
+            F: Fn(&str) -> Option<T>,
+        {
+            let value = pass
+                .fields
+                .get(&field)
+                .ok_or(NormalizeError { field, value: None })?;
+
+            parse(value).ok_or_else(|| NormalizeError {
+                field,
+                value: Some(value.to_owned()),
+            })
+        }
+        let year = |value: &str| value.parse().ok();
+
+        Ok(PassportRecord {
+            birth_year: typed(pass, Field::Byr, year)?,
+            issue_year: typed(pass, Field::Iyr, year)?,
+            expiration_year: typed(pass, Field::Eyr, year)?,
+            height: typed(pass, Field::Hgt, |value| {
+                if let Some(x) = value.strip_suffix("cm") {
+                    x.parse().ok().map(Height::Cm)
+                } else {
+                    value.strip_suffix("in")?.parse().ok().map(Height::In)
+                }
+            })?,
+            hair_colour: typed(pass, Field::Hcl, |value| {
+                let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+                let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+                Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+            })?,
+            eye_colour: typed(pass, Field::Ecl, |value| value.parse().ok())?,
+            passport_id: typed(pass, Field::Pid, |value| Some(value.to_owned()))?,
+            country_id: pass.fields.get(&Field::Cid).cloned(),
+        })
+    }
+}
+
+/// Every passport valid under `schema`, typed one by one. A schema looser
+/// than the default one can accept values a `PassportRecord` can't hold, so
+/// those passports get their own error without failing the rest.
+pub fn normalize(
+    passports: &[Passport],
+    schema: &Schema,
+) -> Vec<Result<PassportRecord, NormalizeError>> {
+    passports
+        .iter()
+        .filter(|pass| schema.validate(pass).is_valid())
+        .map(PassportRecord::try_from)
+        .collect()
+}
+
+pub fn to_csv(records: &[PassportRecord]) -> String {
+    let mut csv = String::from(
+        "birth_year,issue_year,expiration_year,height,height_unit,hair_colour,eye_colour,passport_id,country_id\n",
+    );
+
+    for r in records {
+        let (height, unit) = r.height.parts();
+
+        csv += &format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            r.birth_year,
+            r.issue_year,
+            r.expiration_year,
+            height,
+            unit,
+            r.hair_colour,
+            r.eye_colour,
+            csv_field(&r.passport_id),
+            csv_field(r.country_id.as_deref().unwrap_or("")),
+        );
+    }
+
+    csv
+}
+
+/// A JSON array with one object per record, each on its own line.
+pub fn to_json(records: &[PassportRecord]) -> String {
+    let objects = records
+        .iter()
+        .map(|r| {
+            let (height, unit) = r.height.parts();
+            let Rgb(red, green, blue) = r.hair_colour;
+
+            format!(
+                "{{\"birth_year\":{},\"issue_year\":{},\"expiration_year\":{},\
+                 \"height\":{{\"value\":{},\"unit\":\"{}\"}},\
+                 \"hair_colour\":{{\"r\":{},\"g\":{},\"b\":{}}},\
+                 \"eye_colour\":\"{}\",\"passport_id\":{},\"country_id\":{}}}",
+                r.birth_year,
+                r.issue_year,
+                r.expiration_year,
+                height,
+                unit,
+                red,
+                green,
+                blue,
+                r.eye_colour,
+                json_string(&r.passport_id),
+                r.country_id
+                    .as_deref()
+                    .map_or_else(|| "null".to_owned(), json_string),
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if objects.is_empty() {
+        "[]".to_owned()
+    } else {
+        format!("[\n{}\n]", objects.join(",\n"))
+    }
+}
+
+#[aoc(day4, part1)]
+pub fn part1(input: &[Passport]) -> usize {
+    input
+        .iter()
+        .filter(|pass| DEFAULT_SCHEMA.validate(pass).has_required_fields())
+        .count()
 }
 
 #[aoc(day4, part2)]
-pub fn part2(input: &[HashMap<Field, String>]) -> usize {
+pub fn part2(input: &[Passport]) -> usize {
     input
         .iter()
-        .fold(0, |acc, pass| acc + is_valid_passport2(pass) as usize)
+        .filter(|pass| DEFAULT_SCHEMA.validate(pass).is_valid())
+        .count()
+}
+
+#[test]
+pub fn test_default_schema() {
+    let schema = Schema::default();
+    let rule = |field| &schema.rules.iter().find(|r| r.field == field).unwrap().rule;
+
+    assert_eq!(schema.rules.len(), 8);
+    assert_eq!(Schema::load("src/day4_schema.txt").unwrap(), schema);
+    assert_eq!(rule(Field::Byr).validate("2002"), Ok(()));
+    assert_eq!(
+        rule(Field::Byr).validate("2003"),
+        Err(Reason::AboveMax(2002))
+    );
+    assert_eq!(rule(Field::Hgt).validate("60in"), Ok(()));
+    assert_eq!(rule(Field::Hgt).validate("190cm"), Ok(()));
+    assert_eq!(
+        rule(Field::Hgt).validate("190in"),
+        Err(Reason::AboveMax(76))
+    );
+    assert_eq!(rule(Field::Hgt).validate("190"), Err(Reason::UnknownUnit));
+    assert_eq!(rule(Field::Hcl).validate("#123abc"), Ok(()));
+    assert_eq!(
+        rule(Field::Hcl).validate("#123abz"),
+        Err(Reason::NotHexColour)
+    );
+    assert_eq!(
+        rule(Field::Hcl).validate("123abc"),
+        Err(Reason::NotHexColour)
+    );
+    assert_eq!(rule(Field::Ecl).validate("brn"), Ok(()));
+    assert_eq!(rule(Field::Ecl).validate("wat"), Err(Reason::NotAllowed));
+    assert_eq!(rule(Field::Pid).validate("000000001"), Ok(()));
+    assert_eq!(
+        rule(Field::Pid).validate("0123456789"),
+        Err(Reason::NotDigits(9))
+    );
+}
+
+#[test]
+pub fn test_schema_errors() {
+    assert_eq!(
+        "byr required int 1920..2002\nfoo optional any".parse::<Schema>(),
+        Err(ParseSchemaError::UnknownField {
+            line: 2,
+            key: "foo".to_owned()
+        })
+    );
+    assert_eq!(
+        "# comment\n\nhgt required unit cm 150-193".parse::<Schema>(),
+        Err(ParseSchemaError::InvalidArgument {
+            line: 3,
+            arg: "150-193".to_owned()
+        })
+    );
+    assert_eq!(
+        "ecl sometimes any".parse::<Schema>(),
+        Err(ParseSchemaError::InvalidRequirement {
+            line: 1,
+            word: "sometimes".to_owned()
+        })
+    );
+}
+
+#[test]
+pub fn test_validation_report() {
+    let passports = input_generator(
+        "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:200cm pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn byr:1931
+hgt:179cm",
+    );
+    let schema = Schema::default();
+    let reports: Vec<ValidationReport> = passports.iter().map(|p| schema.validate(p)).collect();
+
+    assert_eq!(
+        reports[0].to_string(),
+        "eyr: 1972 below min 2020\nhgt: 200cm above max 193\npid: 186cm not 9 digits"
+    );
+    assert_eq!(reports[2].missing, vec![Field::Pid]);
+    assert_eq!(reports[2].to_string(), "pid: missing");
+
+    let summary: ValidationSummary = reports.iter().collect();
+
+    assert_eq!(summary.passports, 3);
+    assert_eq!(summary.valid, 0);
+    assert_eq!(summary.missing.get(&Field::Pid), Some(&1));
+    assert_eq!(
+        summary.invalid.get(&(Field::Eyr, Reason::BelowMin(2020))),
+        Some(&2)
+    );
+}
+
+#[test]
+pub fn test_tolerant_parser() {
+    let passports = input_generator(
+        "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\r
+byr:1937 iyr:2017 cid:147 hgt:183cm\r
+\r
+\r
+hgt:1 byr:1937 byr:1938 zip:12345 oops\r
+\r
+\r
+",
+    );
+
+    assert_eq!(passports.len(), 2);
+    assert!(DEFAULT_SCHEMA.validate(&passports[0]).is_valid());
+
+    let report = DEFAULT_SCHEMA.validate(&passports[1]);
+
+    assert_eq!(passports[1].fields.get(&Field::Byr).unwrap(), "1937");
+    assert_eq!(
+        passports[1].duplicates,
+        vec![(Field::Byr, "1938".to_owned())]
+    );
+    assert_eq!(
+        report.invalid[0].to_string(),
+        "hgt: 1 missing or unknown unit"
+    );
+    assert_eq!(report.duplicates, vec![Field::Byr]);
+    assert_eq!(report.unknown, vec!["zip".to_owned()]);
+    assert_eq!(report.malformed, vec!["oops".to_owned()]);
+    assert!(!report.is_valid());
+}
+
+#[test]
+pub fn test_export() {
+    let passports = input_generator(
+        "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+    );
+    let records = normalize(&passports, &Schema::default())
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].height, Height::In(74));
+    assert_eq!(records[0].hair_colour, Rgb(0x62, 0x3a, 0x2f));
+    assert_eq!(records[1].eye_colour, EyeColour::Blu);
+    assert_eq!(
+        to_csv(&records).lines().nth(2),
+        Some("1989,2014,2029,165,cm,#a97842,blu,896056539,129")
+    );
+    assert_eq!(
+        to_json(&records[..1]),
+        "[\n{\"birth_year\":1980,\"issue_year\":2012,\"expiration_year\":2030,\
+         \"height\":{\"value\":74,\"unit\":\"in\"},\"hair_colour\":{\"r\":98,\"g\":58,\"b\":47},\
+         \"eye_colour\":\"grn\",\"passport_id\":\"087499704\",\"country_id\":null}\n]"
+    );
+    assert_eq!(
+        PassportRecord::try_from(&passports[2]),
+        Err(NormalizeError {
+            field: Field::Hcl,
+            value: Some("dab227".to_owned())
+        })
+    );
+
+    let loose: Schema = "ecl required one-of blu pur".parse().unwrap();
+    let passports = input_generator(
+        "pid:087499704 hgt:74in ecl:pur iyr:2012 eyr:2030 byr:1980 hcl:#623a2f
+
+pid:087499704 hgt:74in ecl:blu iyr:2012 eyr:2030 byr:1980 hcl:#623a2f",
+    );
+    let results = normalize(&passports, &loose);
+    assert_eq!(
+        results[0],
+        Err(NormalizeError {
+            field: Field::Ecl,
+            value: Some("pur".to_owned())
+        })
+    );
+    assert_eq!(
+        results[1].as_ref().map(|r| r.eye_colour),
+        Ok(EyeColour::Blu)
+    );
 }
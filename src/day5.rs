@@ -1,43 +1,421 @@
 use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
-#[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<usize> {
-    input
-        .lines()
-        .map(|seat| {
-            seat.chars()
-                .map(|c| match c {
-                    'F' => '0',
-                    'B' => '1',
-                    'L' => '0',
-                    'R' => '1',
-                    _ => unreachable!(),
-                })
-                .collect::<String>()
+/// How many bits of a boarding pass pick the row (`F`/`B`) and how many pick
+/// the column (`L`/`R`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PlaneLayout {
+    pub row_bits: u32,
+    pub column_bits: u32,
+}
+
+impl PlaneLayout {
+    pub const STANDARD: PlaneLayout = PlaneLayout::new(7, 3);
+
+    pub const fn new(row_bits: u32, column_bits: u32) -> Self {
+        assert!(row_bits + column_bits <= 32, "seat ids must fit in 32 bits");
+
+        Self {
+            row_bits,
+            column_bits,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        1 << self.row_bits
+    }
+
+    pub fn columns(&self) -> usize {
+        1 << self.column_bits
+    }
+
+    pub fn code_len(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BoardingPassError {
+    WrongLength { expected: usize, found: usize },
+    InvalidLetter { position: usize, c: char },
+    SeatOutOfRange { row: usize, column: usize },
+}
+
+impl fmt::Display for BoardingPassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BoardingPassError::WrongLength { expected, found } => {
+                write!(f, "expected {} letters, found {}", expected, found)
+            }
+            BoardingPassError::InvalidLetter { position, c } => {
+                write!(f, "unexpected letter {:?} at position {}", c, position)
+            }
+            BoardingPassError::SeatOutOfRange { row, column } => {
+                write!(f, "row {}, column {} is not on the plane", row, column)
+            }
+        }
+    }
+}
+
+impl Error for BoardingPassError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BoardingPass {
+    pub layout: PlaneLayout,
+    pub row: usize,
+    pub column: usize,
+}
+
+impl BoardingPass {
+    pub fn new(layout: PlaneLayout, row: usize, column: usize) -> Result<Self, BoardingPassError> {
+        if row >= layout.rows() || column >= layout.columns() {
+            Err(BoardingPassError::SeatOutOfRange { row, column })
+        } else {
+            Ok(Self {
+                layout,
+                row,
+                column,
+            })
+        }
+    }
+
+    pub fn decode(code: &str, layout: PlaneLayout) -> Result<Self, BoardingPassError> {
+        let found = code.chars().count();
+        if found != layout.code_len() {
+            return Err(BoardingPassError::WrongLength {
+                expected: layout.code_len(),
+                found,
+            });
+        }
+
+        let mut row = 0;
+        let mut column = 0;
+        for (position, c) in code.chars().enumerate() {
+            let is_row = position < layout.row_bits as usize;
+
+            match c {
+                'F' | 'B' if is_row => row = row * 2 + (c == 'B') as usize,
+                'L' | 'R' if !is_row => column = column * 2 + (c == 'R') as usize,
+                _ => return Err(BoardingPassError::InvalidLetter { position, c }),
+            }
+        }
+
+        Ok(Self {
+            layout,
+            row,
+            column,
         })
-        .map(|id_str| usize::from_str_radix(&id_str, 2).unwrap())
-        .collect()
+    }
+
+    pub fn encode(&self) -> String {
+        let bits = |value: usize, n: u32, zero: char, one: char| {
+            (0..n)
+                .rev()
+                .map(move |i| if value >> i & 1 == 1 { one } else { zero })
+        };
+
+        bits(self.row, self.layout.row_bits, 'F', 'B')
+            .chain(bits(self.column, self.layout.column_bits, 'L', 'R'))
+            .collect()
+    }
+
+    pub fn seat_id(&self) -> usize {
+        self.row * self.layout.columns() + self.column
+    }
 }
 
-#[aoc(day5, part1)]
-pub fn part1(input: &[usize]) -> usize {
-    input.iter().max().unwrap().to_owned()
+impl FromStr for BoardingPass {
+    type Err = BoardingPassError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        BoardingPass::decode(code, PlaneLayout::STANDARD)
+    }
 }
 
-#[aoc(day5, part2)]
-pub fn part2(input: &[usize]) -> usize {
-    let occupied_seats: BTreeSet<usize> = input.iter().cloned().collect();
+impl fmt::Display for BoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SeatMapError {
+    LayoutMismatch(BoardingPass),
+    DuplicateSeat(BoardingPass),
+}
+
+impl fmt::Display for SeatMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeatMapError::LayoutMismatch(pass) => {
+                write!(f, "{} was issued for a different plane", pass)
+            }
+            SeatMapError::DuplicateSeat(pass) => write!(f, "{} was issued twice", pass),
+        }
+    }
+}
+
+impl Error for SeatMapError {}
+
+/// Where a free seat sits relative to the occupied ones, by seat id.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Vacancy {
+    /// Before the first occupied seat, where the plane has no seats.
+    Front,
+    /// After the last occupied seat, where the plane has no seats either.
+    Back,
+    /// A real gap between occupied seats.
+    Interior,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FreeSeat {
+    pub pass: BoardingPass,
+    pub vacancy: Vacancy,
+}
+
+/// The occupied seats of a plane, by seat id. Only occupied seats are
+/// stored, so a map costs the same however big its layout is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeatMap {
+    layout: PlaneLayout,
+    occupied: BTreeSet<usize>,
+}
 
-    let mut occupied_seats = occupied_seats.into_iter();
-    let mut last_seat = occupied_seats.next().unwrap();
+impl SeatMap {
+    pub fn new(layout: PlaneLayout, passes: &[BoardingPass]) -> Result<Self, SeatMapError> {
+        let mut occupied = BTreeSet::new();
 
-    for seat_id in occupied_seats {
-        if seat_id != last_seat + 1 {
-            return seat_id - 1;
+        for pass in passes {
+            if pass.layout != layout {
+                return Err(SeatMapError::LayoutMismatch(*pass));
+            }
+            if !occupied.insert(pass.seat_id()) {
+                return Err(SeatMapError::DuplicateSeat(*pass));
+            }
         }
 
-        last_seat = seat_id;
+        Ok(Self { layout, occupied })
     }
 
-    panic!("Oh boi, this shouldn't have happend!");
+    pub fn layout(&self) -> PlaneLayout {
+        self.layout
+    }
+
+    pub fn seat_count(&self) -> usize {
+        self.layout.rows() * self.layout.columns()
+    }
+
+    pub fn is_occupied(&self, seat_id: usize) -> bool {
+        self.occupied.contains(&seat_id)
+    }
+
+    fn pass(&self, seat_id: usize) -> BoardingPass {
+        BoardingPass {
+            layout: self.layout,
+            row: seat_id / self.layout.columns(),
+            column: seat_id % self.layout.columns(),
+        }
+    }
+
+    /// The first and last occupied seat ids, or an empty range when no
+    /// seat is occupied so that every seat counts as `Vacancy::Front`.
+    fn occupied_span(&self) -> (usize, usize) {
+        match (self.occupied.first(), self.occupied.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => (usize::MAX, 0),
+        }
+    }
+
+    fn classify(seat_id: usize, (first, last): (usize, usize)) -> Vacancy {
+        if seat_id < first {
+            Vacancy::Front
+        } else if seat_id > last {
+            Vacancy::Back
+        } else {
+            Vacancy::Interior
+        }
+    }
+
+    pub fn vacancy(&self, seat_id: usize) -> Option<Vacancy> {
+        if seat_id >= self.seat_count() || self.is_occupied(seat_id) {
+            None
+        } else {
+            Some(Self::classify(seat_id, self.occupied_span()))
+        }
+    }
+
+    pub fn free_seats(&self) -> impl Iterator<Item = FreeSeat> + '_ {
+        let span = self.occupied_span();
+
+        (0..self.seat_count())
+            .filter(move |id| !self.is_occupied(*id))
+            .map(move |id| FreeSeat {
+                pass: self.pass(id),
+                vacancy: Self::classify(id, span),
+            })
+    }
+
+    /// Free seats whose seat ids are directly between two occupied ones.
+    pub fn enclosed_seats(&self) -> Vec<BoardingPass> {
+        self.occupied
+            .iter()
+            .filter(|id| !self.is_occupied(*id + 1) && self.is_occupied(*id + 2))
+            .map(|id| self.pass(id + 1))
+            .collect()
+    }
+}
+
+/// One line per row: the row number, then `#` for occupied seats, `O` for
+/// interior gaps and `.` for the missing seats at the front and back.
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.occupied_span();
+        let label_width = (self.layout.rows() - 1).to_string().len();
+
+        for row in 0..self.layout.rows() {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:>width$} ", row, width = label_width)?;
+
+            for column in 0..self.layout.columns() {
+                let id = row * self.layout.columns() + column;
+
+                let c = match (self.is_occupied(id), Self::classify(id, span)) {
+                    (true, _) => '#',
+                    (false, Vacancy::Interior) => 'O',
+                    (false, _) => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[aoc_generator(day5)]
+pub fn input_generator(input: &str) -> Result<Vec<BoardingPass>, BoardingPassError> {
+    input.lines().map(str::parse).collect()
+}
+
+#[aoc(day5, part1)]
+pub fn part1(input: &[BoardingPass]) -> usize {
+    input.iter().map(BoardingPass::seat_id).max().unwrap()
+}
+
+/// The seat id of the first free seat between two occupied ones, or `None`
+/// if every gap in `passes` is at the front or back of the plane.
+pub fn find_my_seat(passes: &[BoardingPass]) -> Result<Option<usize>, SeatMapError> {
+    let map = SeatMap::new(PlaneLayout::STANDARD, passes)?;
+
+    Ok(map.enclosed_seats().first().map(BoardingPass::seat_id))
+}
+
+#[aoc(day5, part2)]
+pub fn part2(input: &[BoardingPass]) -> Result<usize, Box<dyn Error>> {
+    Ok(find_my_seat(input)?.ok_or("no free seat between two occupied ones")?)
+}
+
+#[test]
+pub fn test_decode() {
+    let pass: BoardingPass = "FBFBBFFRLR".parse().unwrap();
+
+    assert_eq!((pass.row, pass.column, pass.seat_id()), (44, 5, 357));
+    assert_eq!(pass.encode(), "FBFBBFFRLR");
+    assert_eq!(
+        "BBFFBBFRLL".parse::<BoardingPass>().map(|p| p.seat_id()),
+        Ok(820)
+    );
+}
+
+#[test]
+pub fn test_other_layouts() {
+    let layout = PlaneLayout::new(4, 2);
+    let pass = BoardingPass::new(layout, 9, 2).unwrap();
+
+    assert_eq!(pass.encode(), "BFFBRL");
+    assert_eq!(BoardingPass::decode("BFFBRL", layout), Ok(pass));
+    assert_eq!(pass.seat_id(), 38);
+    assert_eq!(
+        BoardingPass::new(layout, 16, 0),
+        Err(BoardingPassError::SeatOutOfRange { row: 16, column: 0 })
+    );
+    assert_eq!(
+        BoardingPass::decode("FBFBBFFRLR", layout),
+        Err(BoardingPassError::WrongLength {
+            expected: 6,
+            found: 10
+        })
+    );
+    assert_eq!(
+        BoardingPass::decode("FBFRLL", layout),
+        Err(BoardingPassError::InvalidLetter {
+            position: 3,
+            c: 'R'
+        })
+    );
+}
+
+#[test]
+pub fn test_seat_map() {
+    let layout = PlaneLayout::new(2, 2);
+    let passes: Vec<_> = [(0, 3), (1, 0), (1, 2), (2, 0), (2, 1), (2, 3)]
+        .iter()
+        .map(|&(row, column)| BoardingPass::new(layout, row, column).unwrap())
+        .collect();
+    let map = SeatMap::new(layout, &passes).unwrap();
+
+    let free: Vec<_> = map
+        .free_seats()
+        .map(|seat| (seat.pass.seat_id(), seat.vacancy))
+        .collect();
+    assert_eq!(
+        free,
+        vec![
+            (0, Vacancy::Front),
+            (1, Vacancy::Front),
+            (2, Vacancy::Front),
+            (5, Vacancy::Interior),
+            (7, Vacancy::Interior),
+            (10, Vacancy::Interior),
+            (12, Vacancy::Back),
+            (13, Vacancy::Back),
+            (14, Vacancy::Back),
+            (15, Vacancy::Back),
+        ]
+    );
+    assert_eq!(map.vacancy(7), Some(Vacancy::Interior));
+    assert_eq!(map.vacancy(4), None);
+    assert_eq!(
+        map.enclosed_seats()
+            .iter()
+            .map(BoardingPass::seat_id)
+            .collect::<Vec<_>>(),
+        vec![5, 7, 10]
+    );
+    assert_eq!(map.to_string(), "0 ...#\n1 #O#O\n2 ##O#\n3 ....");
+    assert_eq!(
+        SeatMap::new(layout, &[passes[0], passes[0]]),
+        Err(SeatMapError::DuplicateSeat(passes[0]))
+    );
+}
+
+#[test]
+pub fn test_find_my_seat() {
+    let passes: Vec<BoardingPass> = ["FFFFFFFLLL", "FFFFFFFLRL", "FFFFFFFRLL"]
+        .iter()
+        .map(|code| code.parse().unwrap())
+        .collect();
+
+    assert_eq!(find_my_seat(&passes[1..]), Ok(Some(3)));
+    assert_eq!(find_my_seat(&passes[..1]), Ok(None));
+    assert_eq!(
+        find_my_seat(&[passes[0], passes[0]]),
+        Err(SeatMapError::DuplicateSeat(passes[0]))
+    );
+    assert!(part2(&[passes[0], passes[0]]).is_err());
 }
@@ -1,48 +1,394 @@
-use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+use std::str::FromStr;
 
-#[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Vec<String>> {
-    input
-        .split("\n\n")
-        .map(|group| {
-            group
-                .split('\n')
-                .map(|member| member.to_owned())
-                .collect::<Vec<String>>()
+use crate::records::records;
+
+const QUESTIONS: usize = 26;
+
+/// A set of questions `a` to `z`, one bit each.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Answers(u32);
+
+impl Answers {
+    pub const NONE: Answers = Answers(0);
+    pub const ALL: Answers = Answers((1 << QUESTIONS) - 1);
+
+    fn bit(c: char) -> Option<u32> {
+        if c.is_ascii_lowercase() {
+            Some(1 << (c as u8 - b'a'))
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(self, c: char) -> bool {
+        Self::bit(c).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    pub fn is_subset(self, other: Answers) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn questions(self) -> impl Iterator<Item = char> {
+        (b'a'..=b'z')
+            .map(char::from)
+            .filter(move |c| self.contains(*c))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseAnswersError(pub char);
+
+impl fmt::Display for ParseAnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a question", self.0)
+    }
+}
+
+impl Error for ParseAnswersError {}
+
+impl FromStr for Answers {
+    type Err = ParseAnswersError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().try_fold(Answers::NONE, |acc, c| {
+            Self::bit(c)
+                .map(|bit| Answers(acc.0 | bit))
+                .ok_or(ParseAnswersError(c))
         })
-        .collect()
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.questions().try_for_each(|c| write!(f, "{}", c))
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Answers;
+
+    fn bitor(self, rhs: Answers) -> Answers {
+        Answers(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Answers;
+
+    fn bitand(self, rhs: Answers) -> Answers {
+        Answers(self.0 & rhs.0)
+    }
+}
+
+impl BitXor for Answers {
+    type Output = Answers;
+
+    fn bitxor(self, rhs: Answers) -> Answers {
+        Answers(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Answers {
+    type Output = Answers;
+
+    fn not(self) -> Answers {
+        Answers(!self.0 & Answers::ALL.0)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GroupAnswers {
+    pub members: Vec<Answers>,
+}
+
+impl GroupAnswers {
+    pub fn parse<'a, I>(members: I) -> Result<Self, ParseAnswersError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let members = members
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { members })
+    }
+
+    /// Questions at least one member answered.
+    pub fn anyone(&self) -> Answers {
+        self.members.iter().fold(Answers::NONE, |acc, x| acc | *x)
+    }
+
+    /// Questions every member answered; nothing for a group with no members.
+    pub fn everyone(&self) -> Answers {
+        if self.members.is_empty() {
+            Answers::NONE
+        } else {
+            self.members.iter().fold(Answers::ALL, |acc, x| acc & *x)
+        }
+    }
+
+    pub fn nobody(&self) -> Answers {
+        !self.anyone()
+    }
+
+    /// How many members answered each question, indexed from `a`.
+    pub fn frequencies(&self) -> [usize; QUESTIONS] {
+        let mut counts = [0; QUESTIONS];
+        for member in &self.members {
+            for (i, count) in counts.iter_mut().enumerate() {
+                *count += (member.0 >> i & 1) as usize;
+            }
+        }
+
+        counts
+    }
+
+    pub fn answered_by_at_least(&self, k: usize) -> Answers {
+        let counts = self.frequencies();
+
+        (0..QUESTIONS)
+            .filter(|i| counts[*i] >= k)
+            .fold(Answers::NONE, |acc, i| Answers(acc.0 | 1 << i))
+    }
+
+    /// Questions exactly one of members `a` and `b` answered.
+    pub fn symmetric_difference(&self, a: usize, b: usize) -> Option<Answers> {
+        Some(*self.members.get(a)? ^ *self.members.get(b)?)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quantifier {
+    Everyone,
+    Anyone,
+    Nobody,
+    AtLeast(usize),
+}
+
+impl Quantifier {
+    fn answers(self, group: &GroupAnswers) -> Answers {
+        match self {
+            Quantifier::Everyone => group.everyone(),
+            Quantifier::Anyone => group.anyone(),
+            Quantifier::Nobody => group.nobody(),
+            Quantifier::AtLeast(k) => group.answered_by_at_least(k),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Clause {
+    pub quantifier: Quantifier,
+    pub questions: Answers,
+}
+
+/// Clauses that must all hold for a group, written like
+/// `everyone answered a but nobody answered z` or
+/// `at least 2 answered xy and anyone answered b`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+impl Query {
+    pub fn matches(&self, group: &GroupAnswers) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| clause.questions.is_subset(clause.quantifier.answers(group)))
+    }
+
+    pub fn select<'a>(
+        &'a self,
+        groups: &'a [GroupAnswers],
+    ) -> impl Iterator<Item = &'a GroupAnswers> + 'a {
+        groups.iter().filter(move |group| self.matches(group))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseQueryError {
+    Empty,
+    UnknownQuantifier(String),
+    InvalidCount(String),
+    Expected {
+        expected: &'static str,
+        found: Option<String>,
+    },
+    InvalidQuestions(ParseAnswersError),
+}
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseQueryError::Empty => write!(f, "the query is empty"),
+            ParseQueryError::UnknownQuantifier(word) => {
+                write!(
+                    f,
+                    "expected everyone, anyone, nobody or at least, found {:?}",
+                    word
+                )
+            }
+            ParseQueryError::InvalidCount(word) => write!(f, "{:?} is not a count", word),
+            ParseQueryError::Expected {
+                expected,
+                found: Some(word),
+            } => write!(f, "expected {}, found {:?}", expected, word),
+            ParseQueryError::Expected {
+                expected,
+                found: None,
+            } => write!(f, "expected {}, found the end of the query", expected),
+            ParseQueryError::InvalidQuestions(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ParseQueryError {}
+
+fn next_word<'a, I>(words: &mut I, expected: &'static str) -> Result<&'a str, ParseQueryError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    words.next().ok_or(ParseQueryError::Expected {
+        expected,
+        found: None,
+    })
+}
+
+fn keyword<'a, I>(words: &mut I, keyword: &'static str) -> Result<(), ParseQueryError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    match next_word(words, keyword)? {
+        word if word == keyword => Ok(()),
+        word => Err(ParseQueryError::Expected {
+            expected: keyword,
+            found: Some(word.to_owned()),
+        }),
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let mut clauses = vec![];
+
+        if s.trim().is_empty() {
+            return Err(ParseQueryError::Empty);
+        }
+
+        loop {
+            let quantifier = match next_word(&mut words, "a quantifier")? {
+                "everyone" => Quantifier::Everyone,
+                "anyone" => Quantifier::Anyone,
+                "nobody" => Quantifier::Nobody,
+                "at" => {
+                    keyword(&mut words, "least")?;
+                    let count = next_word(&mut words, "a count")?;
+
+                    Quantifier::AtLeast(
+                        count
+                            .parse()
+                            .map_err(|_| ParseQueryError::InvalidCount(count.to_owned()))?,
+                    )
+                }
+                word => return Err(ParseQueryError::UnknownQuantifier(word.to_owned())),
+            };
+            keyword(&mut words, "answered")?;
+            let questions = next_word(&mut words, "questions")?
+                .parse()
+                .map_err(ParseQueryError::InvalidQuestions)?;
+
+            clauses.push(Clause {
+                quantifier,
+                questions,
+            });
+
+            match words.next() {
+                None => break,
+                Some("and") | Some("but") => continue,
+                Some(word) => {
+                    return Err(ParseQueryError::Expected {
+                        expected: "and or but",
+                        found: Some(word.to_owned()),
+                    })
+                }
+            }
+        }
+
+        Ok(Self { clauses })
+    }
+}
+
+#[aoc_generator(day6)]
+pub fn input_generator(input: &str) -> Result<Vec<GroupAnswers>, ParseAnswersError> {
+    records(input).map(GroupAnswers::parse).collect()
 }
 
 #[aoc(day6, part1)]
-pub fn part1(input: &[Vec<String>]) -> usize {
-    input
-        .iter()
-        .map(|group| {
-            group
-                .iter()
-                .map(|member| member.chars())
-                .flatten()
-                .collect::<HashSet<char>>()
-                .len()
-        })
-        .sum()
+pub fn part1(input: &[GroupAnswers]) -> usize {
+    input.iter().map(|group| group.anyone().len()).sum()
 }
 
 #[aoc(day6, part2)]
-pub fn part2(input: &[Vec<String>]) -> usize {
-    input
-        .iter()
-        .map(|group| {
-            let mut answers = group
-                .iter()
-                .map(|member| member.chars().collect::<HashSet<char>>());
-
-            let first_answer = answers.next().unwrap();
-            answers
-                .fold(first_answer, |acc, x| {
-                    acc.intersection(&x).cloned().collect()
-                })
-                .len()
+pub fn part2(input: &[GroupAnswers]) -> usize {
+    input.iter().map(|group| group.everyone().len()).sum()
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
+
+#[test]
+pub fn test_group_answers() {
+    let groups = input_generator(EXAMPLE).unwrap();
+    let group = &groups[2];
+
+    assert_eq!(part1(&groups), 11);
+    assert_eq!(part2(&groups), 6);
+    assert_eq!(
+        part2(&input_generator(&EXAMPLE.replace('\n', "\r\n")).unwrap()),
+        6
+    );
+    assert_eq!(group.frequencies()[..3], [2, 1, 1]);
+    assert_eq!(group.answered_by_at_least(2).to_string(), "a");
+    assert_eq!(group.symmetric_difference(0, 1).unwrap().to_string(), "bc");
+    assert_eq!(group.symmetric_difference(0, 2), None);
+    assert_eq!(group.nobody().len(), 23);
+    assert_eq!("ab1".parse::<Answers>(), Err(ParseAnswersError('1')));
+}
+
+#[test]
+pub fn test_query() {
+    let groups = input_generator(EXAMPLE).unwrap();
+    let count = |query: &str| query.parse::<Query>().unwrap().select(&groups).count();
+
+    assert_eq!(count("everyone answered a but nobody answered z"), 3);
+    assert_eq!(count("anyone answered bc and nobody answered a"), 0);
+    assert_eq!(count("at least 2 answered a"), 2);
+    assert_eq!(count("anyone answered b but at least 1 answered c"), 3);
+    assert_eq!("".parse::<Query>(), Err(ParseQueryError::Empty));
+    assert_eq!(
+        "everyone said a".parse::<Query>(),
+        Err(ParseQueryError::Expected {
+            expected: "answered",
+            found: Some("said".to_owned())
         })
-        .sum()
+    );
+    assert_eq!(
+        "most answered a".parse::<Query>(),
+        Err(ParseQueryError::UnknownQuantifier("most".to_owned()))
+    );
 }
@@ -39,7 +39,7 @@ fn contains_shiny(
             None => false,
             Some(bags) => {
                 for (bag, _) in bags.iter() {
-                    if contains_shiny(input, memo, &bag) {
+                    if contains_shiny(input, memo, bag) {
                         return true;
                     }
                 }
@@ -77,7 +77,7 @@ pub fn count_inner_bags(input: &HashMap<String, Vec<(String, u32)>>, bag: &str)
         None => 0,
         Some(bags) => {
             if bags.is_empty() {
-                return 0;
+                0
             } else {
                 bags.iter().fold(0, |acc, (bag, amount)| {
                     let amount = *amount as usize;
@@ -104,7 +104,7 @@ dark olive bags contain 3 faded blue bags, 4 dotted black bags.
 vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
 faded blue bags contain no other bags.
 dotted black bags contain no other bags.";
-    assert_eq!(part1(&input_generator(&input)), 4);
+    assert_eq!(part1(&input_generator(input)), 4);
 }
 
 #[test]
@@ -116,5 +116,5 @@ dark yellow bags contain 2 dark green bags.
 dark green bags contain 2 dark blue bags.
 dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
-    assert_eq!(part2(&input_generator(&input)), 126);
+    assert_eq!(part2(&input_generator(input)), 126);
 }
@@ -1,4 +1,209 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+/// A number that isn't the sum of two different numbers among the ones right
+/// before it, along with its position in the stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Invalid {
+    pub index: usize,
+    pub value: u64,
+}
+
+/// Checks an XMAS stream one number at a time, remembering only the last
+/// `window` numbers. The first `window` numbers are the preamble and are
+/// always valid.
+#[derive(Clone, Debug)]
+pub struct XmasValidator {
+    window: usize,
+    recent: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+    seen: usize,
+}
+
+impl XmasValidator {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: HashMap::new(),
+            seen: 0,
+        }
+    }
+
+    pub fn is_valid(&self, x: u64) -> bool {
+        if self.seen < self.window {
+            return true;
+        }
+
+        self.recent.iter().any(|y| match x.checked_sub(*y) {
+            Some(z) => z != *y && self.counts.contains_key(&z),
+            None => false,
+        })
+    }
+
+    /// Checks `x` against the current window, then slides the window over it.
+    pub fn push(&mut self, x: u64) -> Result<(), Invalid> {
+        let valid = self.is_valid(x);
+        let index = self.seen;
+
+        self.seen += 1;
+        self.recent.push_back(x);
+        *self.counts.entry(x).or_insert(0) += 1;
+        if self.recent.len() > self.window {
+            let old = self.recent.pop_front().unwrap();
+            if let Some(count) = self.counts.get_mut(&old) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&old);
+                }
+            }
+        }
+
+        if valid {
+            Ok(())
+        } else {
+            Err(Invalid { index, value: x })
+        }
+    }
+
+    /// Lazily yields every invalid number of `numbers`.
+    pub fn invalid_numbers<I>(self, numbers: I) -> InvalidNumbers<I::IntoIter>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        InvalidNumbers {
+            validator: self,
+            numbers: numbers.into_iter(),
+        }
+    }
+
+    /// Every invalid number of a stream with one number per line.
+    pub fn check_reader<R: BufRead>(mut self, reader: R) -> Result<Vec<Invalid>, ReadNumbersError> {
+        let mut invalid = vec![];
+        for x in read_numbers(reader) {
+            if let Err(e) = self.push(x?) {
+                invalid.push(e);
+            }
+        }
+
+        Ok(invalid)
+    }
+}
+
+pub struct InvalidNumbers<I> {
+    validator: XmasValidator,
+    numbers: I,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for InvalidNumbers<I> {
+    type Item = Invalid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let validator = &mut self.validator;
+
+        self.numbers.find_map(|x| validator.push(x).err())
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadNumbersError {
+    Io(io::Error),
+    NotANumber { line: usize, value: String },
+}
+
+impl fmt::Display for ReadNumbersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadNumbersError::Io(e) => write!(f, "{}", e),
+            ReadNumbersError::NotANumber { line, value } => {
+                write!(f, "line {}: {:?} is not a number", line, value)
+            }
+        }
+    }
+}
+
+impl Error for ReadNumbersError {}
+
+impl From<io::Error> for ReadNumbersError {
+    fn from(e: io::Error) -> Self {
+        ReadNumbersError::Io(e)
+    }
+}
+
+/// The numbers of `reader`, one per line, skipping blank lines.
+pub fn read_numbers<R: BufRead>(reader: R) -> impl Iterator<Item = Result<u64, ReadNumbersError>> {
+    reader.lines().enumerate().filter_map(|(i, line)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let value = line.trim();
+
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.parse().map_err(|_| ReadNumbersError::NotANumber {
+                line: i + 1,
+                value: value.to_owned(),
+            }))
+        }
+    })
+}
+
+/// A run of consecutive numbers, as positions `range` in the input, and its
+/// smallest and largest values.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContiguousRange {
+    pub range: Range<usize>,
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Every run of at least `min_len` consecutive numbers adding up to
+/// `target`, ordered by start then end, or `None` if there are none. Sums are
+/// looked up through prefix sums, so only the matching runs are walked.
+pub fn find_contiguous_ranges(
+    numbers: &[u64],
+    target: u64,
+    min_len: usize,
+) -> Option<Vec<ContiguousRange>> {
+    // Prefix sums as u128 can't overflow for any slice that fits in memory
+    let mut starts: HashMap<u128, Vec<usize>> = HashMap::new();
+    let mut prefix = 0_u128;
+    let mut ranges = vec![];
+
+    for (end, x) in numbers.iter().enumerate() {
+        starts.entry(prefix).or_default().push(end);
+        prefix += u128::from(*x);
+
+        let wanted = match prefix.checked_sub(u128::from(target)) {
+            Some(wanted) => wanted,
+            None => continue,
+        };
+        for &start in starts.get(&wanted).into_iter().flatten() {
+            if end + 1 - start < min_len {
+                break;
+            }
+
+            let run = &numbers[start..=end];
+            ranges.push(ContiguousRange {
+                range: start..end + 1,
+                min: *run.iter().min().unwrap(),
+                max: *run.iter().max().unwrap(),
+            });
+        }
+    }
+
+    if ranges.is_empty() {
+        None
+    } else {
+        ranges.sort_by_key(|r| (r.range.start, r.range.end));
+        Some(ranges)
+    }
+}
 
 #[aoc_generator(day9)]
 pub fn input_generator(input: &str) -> Vec<u64> {
@@ -6,55 +211,102 @@ pub fn input_generator(input: &str) -> Vec<u64> {
 }
 
 #[aoc(day9, part1)]
-pub fn part1(input: &Vec<u64>) -> u64 {
-    let mut prev_nums: HashSet<u64> = HashSet::new();
-    input[0..25].iter().for_each(|n| {
-        prev_nums.insert(*n);
-    });
-
-    let mut i = 25;
-    loop {
-        let x = input[i];
-        // Is x = y + z where y and z are in `prev_nums`
-        let z = input[i - 25..i].iter().find(|y| {
-            let z = x - **y;
-            prev_nums.contains(&z)
-        });
-
-        match z {
-            Some(_) => {
-                prev_nums.insert(x);
-                prev_nums.remove(&input[i - 25]);
-                i += 1;
-            }
-            None => break x,
-        }
-    }
+pub fn part1(input: &[u64]) -> Option<u64> {
+    XmasValidator::new(25)
+        .invalid_numbers(input.iter().cloned())
+        .next()
+        .map(|invalid| invalid.value)
 }
 
 #[aoc(day9, part2)]
-pub fn part2(input: &Vec<u64>) -> u64 {
-    let x = part1(input);
+pub fn part2(input: &[u64]) -> Option<u64> {
+    let ranges = find_contiguous_ranges(input, part1(input)?, 2)?;
+
+    ranges[0].min.checked_add(ranges[0].max)
+}
 
-    let mut left = 0;
+#[cfg(test)]
+const EXAMPLE: [u64; 20] = [
+    35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+];
 
-    loop {
-        let mut sum = input[left];
-        let mut right = left;
+#[test]
+pub fn test_validator() {
+    let invalid: Vec<_> = XmasValidator::new(5)
+        .invalid_numbers(EXAMPLE.to_vec())
+        .collect();
 
-        while sum < x {
-            right += 1;
-            sum += input[right];
+    assert_eq!(
+        invalid[0],
+        Invalid {
+            index: 14,
+            value: 127
         }
+    );
+    assert_eq!(invalid.len(), 1);
 
-        if sum == x {
-            let mut window = vec![0; right - left + 1];
-            window.copy_from_slice(&input[left..=right]);
-            window.sort();
+    // Window values bigger than the number checked mustn't underflow
+    let invalid: Vec<_> = XmasValidator::new(2)
+        .invalid_numbers(vec![1, 2, 3, 2, 1, 10])
+        .collect();
+    assert_eq!(
+        invalid,
+        vec![
+            Invalid { index: 3, value: 2 },
+            Invalid { index: 4, value: 1 },
+            Invalid {
+                index: 5,
+                value: 10
+            },
+        ]
+    );
+}
 
-            break window.first().unwrap() + window.last().unwrap();
-        }
+#[test]
+pub fn test_check_reader() {
+    let input = EXAMPLE
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
 
-        left += 1;
+    let invalid = XmasValidator::new(5)
+        .check_reader(input.as_bytes())
+        .unwrap();
+    assert_eq!(
+        invalid,
+        vec![Invalid {
+            index: 14,
+            value: 127
+        }]
+    );
+
+    match XmasValidator::new(5).check_reader("1\n\nx".as_bytes()) {
+        Err(ReadNumbersError::NotANumber { line, value }) => {
+            assert_eq!((line, value.as_str()), (3, "x"))
+        }
+        other => panic!("unexpected {:?}", other),
     }
 }
+
+#[test]
+pub fn test_find_contiguous_ranges() {
+    let ranges = find_contiguous_ranges(&EXAMPLE, 127, 2).unwrap();
+
+    assert_eq!(
+        ranges,
+        vec![ContiguousRange {
+            range: 2..6,
+            min: 15,
+            max: 47
+        }]
+    );
+    assert_eq!(find_contiguous_ranges(&EXAMPLE, 127, 1).unwrap().len(), 2);
+    assert_eq!(find_contiguous_ranges(&EXAMPLE, 1, 1), None);
+
+    let ranges = find_contiguous_ranges(&[1, 0, 1, 0, u64::MAX, 2], 2, 2).unwrap();
+    assert_eq!(
+        ranges.iter().map(|r| r.range.clone()).collect::<Vec<_>>(),
+        vec![0..3, 0..4]
+    );
+}
@@ -0,0 +1,391 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+pub type Pos = (isize, isize);
+
+pub const ORTHOGONAL: [Pos; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+pub const ADJACENT: [Pos; 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseGridError {
+    Empty,
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCell {
+        row: usize,
+        col: usize,
+        c: char,
+    },
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseGridError::Empty => write!(f, "the grid is empty"),
+            ParseGridError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells, expected {} like the first row",
+                row, found, expected
+            ),
+            ParseGridError::InvalidCell { row, col, c } => {
+                write!(f, "invalid cell {:?} at row {}, column {}", c, row, col)
+            }
+        }
+    }
+}
+
+impl Error for ParseGridError {}
+
+/// What happens to a coordinate that falls outside the grid along one axis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edge {
+    Wrap,
+    Clamp,
+    Reflect,
+    Stop,
+}
+
+impl Edge {
+    fn resolve(self, v: isize, len: usize) -> Option<usize> {
+        let len = len as isize;
+
+        match self {
+            Edge::Wrap => Some(v.rem_euclid(len) as usize),
+            Edge::Clamp => Some(v.clamp(0, len - 1) as usize),
+            Edge::Reflect => {
+                let v = v.rem_euclid(2 * len);
+
+                Some(if v < len { v } else { 2 * len - 1 - v } as usize)
+            }
+            Edge::Stop if (0..len).contains(&v) => Some(v as usize),
+            Edge::Stop => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Addressing {
+    pub x: Edge,
+    pub y: Edge,
+}
+
+impl Addressing {
+    pub const TOROIDAL: Addressing = Addressing::new(Edge::Wrap, Edge::Wrap);
+    pub const CLAMPED: Addressing = Addressing::new(Edge::Clamp, Edge::Clamp);
+    pub const BOUNDED: Addressing = Addressing::new(Edge::Stop, Edge::Stop);
+
+    pub const fn new(x: Edge, y: Edge) -> Self {
+        Self { x, y }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn parse<F>(input: &str, cell: F) -> Result<Self, ParseGridError>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let mut width = 0;
+        let mut height = 0;
+        let mut data = vec![];
+
+        for (row, line) in input.lines().enumerate() {
+            let len = data.len();
+            for (col, c) in line.chars().enumerate() {
+                data.push(cell(c).ok_or(ParseGridError::InvalidCell { row, col, c })?);
+            }
+
+            let found = data.len() - len;
+            if row == 0 {
+                width = found;
+            } else if found != width {
+                return Err(ParseGridError::RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+            height += 1;
+        }
+
+        if data.is_empty() {
+            Err(ParseGridError::Empty)
+        } else {
+            Ok(Self {
+                width,
+                height,
+                data,
+            })
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn pos_of(&self, idx: usize) -> Pos {
+        ((idx % self.width) as isize, (idx / self.width) as isize)
+    }
+
+    /// Brings `pos` inside the grid, or `None` if it falls off an edge
+    /// addressed with `Edge::Stop`.
+    pub fn resolve(&self, pos: Pos, addressing: Addressing) -> Option<Pos> {
+        let x = addressing.x.resolve(pos.0, self.width)?;
+        let y = addressing.y.resolve(pos.1, self.height)?;
+
+        Some((x as isize, y as isize))
+    }
+
+    pub fn index_of(&self, pos: Pos, addressing: Addressing) -> Option<usize> {
+        let (x, y) = self.resolve(pos, addressing)?;
+
+        Some(self.resolved_index((x, y)))
+    }
+
+    fn resolved_index(&self, (x, y): Pos) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    pub fn get(&self, pos: Pos, addressing: Addressing) -> Option<&T> {
+        self.index_of(pos, addressing).map(|i| &self.data[i])
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        self.data.chunks(self.width).nth(y)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.data.chunks(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T> + '_> {
+        if x < self.width {
+            Some(self.data.iter().skip(x).step_by(self.width))
+        } else {
+            None
+        }
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |x| self.data.iter().skip(x).step_by(self.width))
+    }
+
+    pub fn neighbours<'a>(
+        &'a self,
+        pos: Pos,
+        offsets: &'a [Pos],
+        addressing: Addressing,
+    ) -> impl Iterator<Item = (Pos, &'a T)> + 'a {
+        offsets.iter().filter_map(move |(dx, dy)| {
+            let pos = self.resolve((pos.0 + dx, pos.1 + dy), addressing)?;
+
+            Some((pos, &self.data[self.resolved_index(pos)]))
+        })
+    }
+
+    pub fn neighbours4(&self, pos: Pos, addressing: Addressing) -> impl Iterator<Item = (Pos, &T)> {
+        self.neighbours(pos, &ORTHOGONAL, addressing)
+    }
+
+    pub fn neighbours8(&self, pos: Pos, addressing: Addressing) -> impl Iterator<Item = (Pos, &T)> {
+        self.neighbours(pos, &ADJACENT, addressing)
+    }
+
+    /// Cells met walking from `pos` (excluded) in steps of `dir`. The walk
+    /// ends at an `Edge::Stop` border, or as soon as an `Edge::Clamp` border
+    /// would pull it off its line, and turns back along an axis when it hits
+    /// an `Edge::Reflect` border. With `Edge::Wrap` or `Edge::Reflect` on
+    /// every axis it only ends if it's stuck on one cell.
+    pub fn ray(&self, pos: Pos, dir: Pos, addressing: Addressing) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            pos,
+            dir,
+            addressing,
+        }
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        let i = self
+            .index_of(pos, Addressing::BOUNDED)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos));
+
+        &self.data[i]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut Self::Output {
+        let i = self
+            .index_of(pos, Addressing::BOUNDED)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos));
+
+        &mut self.data[i]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    pos: Pos,
+    dir: Pos,
+    addressing: Addressing,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = (Pos, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let leaves =
+            |edge, v: isize, len: usize| edge == Edge::Reflect && !(0..len as isize).contains(&v);
+        if leaves(self.addressing.x, self.pos.0 + self.dir.0, self.grid.width) {
+            self.dir.0 = -self.dir.0;
+        }
+        if leaves(self.addressing.y, self.pos.1 + self.dir.1, self.grid.height) {
+            self.dir.1 = -self.dir.1;
+        }
+
+        let next = (self.pos.0 + self.dir.0, self.pos.1 + self.dir.1);
+        let pos = self.grid.resolve(next, self.addressing)?;
+
+        let clamped = |edge, resolved, wanted| edge == Edge::Clamp && resolved != wanted;
+        if pos == self.pos
+            || clamped(self.addressing.x, pos.0, next.0)
+            || clamped(self.addressing.y, pos.1, next.1)
+        {
+            return None;
+        }
+        self.pos = pos;
+
+        Some((pos, &self.grid.data[self.grid.resolved_index(pos)]))
+    }
+}
+
+#[test]
+pub fn test_parse_errors() {
+    let digit = |c: char| c.to_digit(10);
+
+    assert_eq!(Grid::parse("", digit), Err(ParseGridError::Empty));
+    assert_eq!(
+        Grid::parse("123\n45\n678", digit),
+        Err(ParseGridError::RaggedRow {
+            row: 1,
+            expected: 3,
+            found: 2
+        })
+    );
+    assert_eq!(
+        Grid::parse("123\n4x6", digit),
+        Err(ParseGridError::InvalidCell {
+            row: 1,
+            col: 1,
+            c: 'x'
+        })
+    );
+}
+
+#[test]
+pub fn test_addressing_and_views() {
+    let grid = Grid::parse("123\n456", |c| c.to_digit(10)).unwrap();
+
+    assert_eq!(grid.get((4, -1), Addressing::TOROIDAL), Some(&5));
+    assert_eq!(grid.get((4, -1), Addressing::CLAMPED), Some(&3));
+    assert_eq!(grid.get((4, -1), Addressing::BOUNDED), None);
+    assert_eq!(
+        grid.get((4, -1), Addressing::new(Edge::Reflect, Edge::Reflect)),
+        Some(&2)
+    );
+    assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+    assert_eq!(grid.column(2).unwrap().collect::<Vec<_>>(), vec![&3, &6]);
+    assert_eq!(grid.neighbours8((0, 0), Addressing::BOUNDED).count(), 3);
+    assert_eq!(grid.to_string(), "123\n456");
+}
+
+#[test]
+pub fn test_ray() {
+    let grid = Grid::parse("123\n456\n789", |c| c.to_digit(10)).unwrap();
+    let cells = |addressing| {
+        grid.ray((0, 0), (1, 1), addressing)
+            .map(|(_, c)| *c)
+            .take(5)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(cells(Addressing::BOUNDED), vec![5, 9]);
+    assert_eq!(cells(Addressing::CLAMPED), vec![5, 9]);
+    assert_eq!(cells(Addressing::TOROIDAL), vec![5, 9, 1, 5, 9]);
+
+    // Reflected rays bounce back off each wall they hit
+    let reflect = Addressing::new(Edge::Reflect, Edge::Reflect);
+    assert_eq!(cells(reflect), vec![5, 9, 5, 1, 5]);
+    let bounce = |pos, dir| {
+        grid.ray(pos, dir, reflect)
+            .map(|(_, c)| *c)
+            .take(5)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bounce((0, 0), (1, 0)), vec![2, 3, 2, 1, 2]);
+    assert_eq!(bounce((0, 1), (1, 1)), vec![8, 6, 2, 4, 8]);
+
+    // Off the corner diagonal the ray hits a side; clamping mustn't slide it
+    let from = |pos| {
+        grid.ray(pos, (1, 1), Addressing::CLAMPED)
+            .map(|(_, c)| *c)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(from((2, 0)), Vec::<u32>::new());
+    assert_eq!(from((0, 1)), vec![8]);
+}
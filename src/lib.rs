@@ -12,6 +12,10 @@ pub mod day11;
 pub mod day12;
 pub mod day13;
 pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
 pub mod day2;
 pub mod day3;
 pub mod day4;
@@ -20,9 +24,8 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
-pub mod day15;
-pub mod day16;
-pub mod day17;
-pub mod day18;
+pub mod export;
+pub mod grid;
+pub mod records;
 
 aoc_lib! { year = 2020 }
@@ -0,0 +1,51 @@
+use std::str::Lines;
+
+/// Records separated by one or more blank lines, each as its lines with line
+/// endings and trailing whitespace removed. Blank lines before the first and
+/// after the last record are ignored, whether they end in `\n` or `\r\n`.
+pub fn records(input: &str) -> Records<'_> {
+    Records {
+        lines: input.lines(),
+    }
+}
+
+pub struct Records<'a> {
+    lines: Lines<'a>,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = vec![];
+
+        for line in self.lines.by_ref().map(str::trim_end) {
+            if !line.is_empty() {
+                record.push(line);
+            } else if !record.is_empty() {
+                break;
+            }
+        }
+
+        if record.is_empty() {
+            None
+        } else {
+            Some(record)
+        }
+    }
+}
+
+#[test]
+pub fn test_records() {
+    let input = "\r\nab \r\nc\r\n\r\n \t\r\n\r\nd\r\n\r\n\r\n";
+
+    assert_eq!(
+        records(input).collect::<Vec<_>>(),
+        vec![vec!["ab", "c"], vec!["d"]]
+    );
+    assert_eq!(
+        records("a\n\nb\nc").collect::<Vec<_>>(),
+        vec![vec!["a"], vec!["b", "c"]]
+    );
+    assert_eq!(records(" \n\n").count(), 0);
+}